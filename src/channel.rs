@@ -0,0 +1,393 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 LNP/BP Laboratories,
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2025 RGB Consortium, Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Verification of the contract operations inside a Lightning channel.
+//!
+//! In a Lightning channel, RGB operations are committed in commitment transactions, which are
+//! constantly replaced and almost never published. A [`Channel`] evaluates consignments for each
+//! new commitment transaction against a read-only on-chain ledger, keeping the operations which
+//! are not known to the ledger in a separate per-commitment state. The state of a commitment is
+//! discarded once the commitment gets revoked, and the ledger is never modified. The channel
+//! doesn't borrow the ledger, which thus may keep evolving while the channel is open.
+
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use amplify::confinement::SmallOrdMap;
+use bp::seals::{TxoSeal, WTxoSeal};
+use bp::Txid;
+use single_use_seals::{PublishedWitness, SealWitness};
+use ultrasonic::{
+    AuthToken, CellAddr, Codex, ContractId, ContractMeta, LibRepo, Memory, Operation, Opid, StateCell, StateValue,
+    VerifiedOperation,
+};
+
+use crate::{ContractApi, ContractVerify, ReadOperation, RgbSealDef, VerificationError, VerifyConfig};
+
+/// Contract state defined by the operations committed in a single channel commitment
+/// transaction, which are not known to the on-chain ledger.
+#[derive(Clone, Default, Debug)]
+pub struct Commitment {
+    operations: BTreeMap<Opid, Operation>,
+    seals: BTreeMap<Opid, BTreeMap<u16, WTxoSeal>>,
+    witnesses: BTreeMap<Opid, SealWitness<TxoSeal>>,
+    destructible: BTreeMap<CellAddr, StateCell>,
+    immutable: BTreeMap<CellAddr, StateValue>,
+}
+
+impl Commitment {
+    /// Removes operations which are known to the `ledger`, together with their seals and
+    /// witnesses.
+    fn prune(&mut self, ledger: &impl ContractApi<TxoSeal>) {
+        self.operations.retain(|opid, _| !ledger.is_known(*opid));
+        self.seals.retain(|opid, _| !ledger.is_known(*opid));
+        self.witnesses.retain(|opid, _| !ledger.is_known(*opid));
+        self.destructible
+            .retain(|addr, _| !ledger.is_known(addr.opid));
+        self.immutable.retain(|addr, _| !ledger.is_known(addr.opid));
+    }
+
+    /// Returns the operations committed in the commitment transaction.
+    pub fn operations(&self) -> &BTreeMap<Opid, Operation> { &self.operations }
+
+    /// Returns the seal definitions for the outputs of the channel operations.
+    pub fn seals(&self) -> &BTreeMap<Opid, BTreeMap<u16, WTxoSeal>> { &self.seals }
+
+    /// Returns the witnesses for the channel operations, which are required to publish the
+    /// commitment on-chain.
+    pub fn witnesses(&self) -> &BTreeMap<Opid, SealWitness<TxoSeal>> { &self.witnesses }
+}
+
+/// Channel-scoped contract state, layered on top of a read-only on-chain ledger.
+///
+/// The ledger is not kept by the channel and is provided to the methods requiring it instead.
+#[derive(Clone, Default, Debug)]
+pub struct Channel {
+    commitments: BTreeMap<Txid, Commitment>,
+    current: Option<Txid>,
+    revoked: BTreeSet<Txid>,
+}
+
+impl Channel {
+    /// Constructs a channel with no commitments.
+    pub fn new() -> Self { Self::default() }
+
+    /// Returns the id of the current commitment transaction.
+    pub fn current(&self) -> Option<Txid> { self.current }
+
+    /// Returns the state of the current commitment transaction on top of the current state of the
+    /// `ledger`.
+    ///
+    /// Operations which became known to the ledger after the commitment was evaluated (for
+    /// instance, since the commitment transaction got published) are not included.
+    pub fn current_commitment(&self, ledger: &impl ContractApi<TxoSeal>) -> Option<Commitment> {
+        let mut commitment = self.commitments.get(&self.current?)?.clone();
+        commitment.prune(ledger);
+        Some(commitment)
+    }
+
+    /// Returns the state of a commitment transaction which was not revoked.
+    pub fn commitment(&self, txid: Txid) -> Option<&Commitment> { self.commitments.get(&txid) }
+
+    /// Evaluates a consignment for a new commitment transaction `txid` against the `ledger`, making
+    /// it the current commitment.
+    ///
+    /// The consignment must contain the full contract history starting from genesis; operations
+    /// known to the ledger are not applied to the channel state. All the other operations having a
+    /// witness must be witnessed by the commitment transaction, which doesn't have to be
+    /// published.
+    ///
+    /// The previous commitment remains valid until it is revoked with [`Self::revoke`].
+    #[allow(clippy::result_large_err)]
+    pub fn update<R: ReadOperation<Seal = TxoSeal>>(
+        &mut self,
+        ledger: &impl ContractApi<TxoSeal>,
+        txid: Txid,
+        reader: R,
+        config: &VerifyConfig,
+    ) -> Result<(), ChannelError> {
+        if self.revoked.contains(&txid) {
+            return Err(ChannelError::Revoked(txid));
+        }
+        let mut api = CommitmentApi {
+            ledger,
+            txid,
+            commitment: Commitment::default(),
+            foreign: None,
+        };
+        api.evaluate_with(reader, config)?;
+        if let Some(found) = api.foreign {
+            return Err(ChannelError::ForeignWitness { expected: txid, found });
+        }
+        self.commitments.insert(txid, api.commitment);
+        self.current = Some(txid);
+        Ok(())
+    }
+
+    /// Revokes a commitment transaction, discarding all the operations it has committed to.
+    ///
+    /// Returns the state of the revoked commitment, if it was known.
+    pub fn revoke(&mut self, txid: Txid) -> Option<Commitment> {
+        self.revoked.insert(txid);
+        if self.current == Some(txid) {
+            self.current = None;
+        }
+        self.commitments.remove(&txid)
+    }
+}
+
+/// Implementation of [`ContractApi`] which evaluates a consignment into a channel commitment state.
+struct CommitmentApi<'l, L: ContractApi<TxoSeal>> {
+    ledger: &'l L,
+    txid: Txid,
+    commitment: Commitment,
+    /// Witness of a channel operation which is not the commitment transaction.
+    foreign: Option<Txid>,
+}
+
+impl<L: ContractApi<TxoSeal>> Memory for CommitmentApi<'_, L> {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> {
+        self.commitment
+            .destructible
+            .get(&addr)
+            .copied()
+            .or_else(|| self.ledger.memory().destructible(addr))
+    }
+
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> {
+        self.commitment
+            .immutable
+            .get(&addr)
+            .copied()
+            .or_else(|| self.ledger.memory().immutable(addr))
+    }
+}
+
+impl<L: ContractApi<TxoSeal>> ContractApi<TxoSeal> for CommitmentApi<'_, L> {
+    fn contract_id(&self) -> ContractId { self.ledger.contract_id() }
+
+    fn meta(&self) -> &ContractMeta { self.ledger.meta() }
+
+    fn codex(&self) -> &Codex { self.ledger.codex() }
+
+    fn repo(&self) -> &impl LibRepo { self.ledger.repo() }
+
+    fn memory(&self) -> &impl Memory { self }
+
+    fn is_known(&self, opid: Opid) -> bool { self.ledger.is_known(opid) }
+
+    fn apply_operation(&mut self, op: VerifiedOperation) {
+        let opid = op.opid();
+        let op = op.into_operation();
+        for (no, cell) in op.destructible_out.iter().enumerate() {
+            self.commitment
+                .destructible
+                .insert(CellAddr::new(opid, no as u16), *cell);
+        }
+        for (no, data) in op.immutable_out.iter().enumerate() {
+            self.commitment
+                .immutable
+                .insert(CellAddr::new(opid, no as u16), data.value);
+        }
+        self.commitment.operations.insert(opid, op);
+    }
+
+    fn apply_seals(&mut self, opid: Opid, seals: SmallOrdMap<u16, WTxoSeal>) {
+        if self.ledger.is_known(opid) {
+            return;
+        }
+        self.commitment.seals.entry(opid).or_default().extend(seals);
+    }
+
+    fn seal_definition(&self, token: AuthToken) -> Option<(CellAddr, WTxoSeal)> {
+        self.commitment
+            .seals
+            .iter()
+            .flat_map(|(opid, seals)| {
+                seals
+                    .iter()
+                    .map(|(pos, seal)| (CellAddr::new(*opid, *pos), *seal))
+            })
+            .find(|(_, seal)| seal.auth_token() == token)
+            .or_else(|| self.ledger.seal_definition(token))
+    }
+
+    fn apply_witness(&mut self, opid: Opid, witness: SealWitness<TxoSeal>) {
+        if self.ledger.is_known(opid) {
+            return;
+        }
+        let txid = witness.published.pub_id();
+        if txid != self.txid {
+            self.foreign.get_or_insert(txid);
+            return;
+        }
+        self.commitment.witnesses.insert(opid, witness);
+    }
+}
+
+/// Errors happening during the evaluation of a channel commitment.
+#[derive(Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum ChannelError {
+    /// commitment transaction {0} was revoked.
+    Revoked(Txid),
+
+    /// channel operation is witnessed by transaction {found} instead of the commitment
+    /// transaction {expected}.
+    #[allow(missing_docs)]
+    ForeignWitness { expected: Txid, found: Txid },
+
+    /// {0}
+    #[from]
+    Verification(VerificationError<TxoSeal>),
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use amplify::{ByteArray, Bytes};
+    use bp::seals::{Noise, TxoSealExt, WOutpoint};
+    use bp::Outpoint;
+    use ultrasonic::StateData;
+
+    use super::*;
+    use crate::stream::ReadOperationExt;
+    use crate::verify::test::{
+        contract, contract_with, genesis, issue, operation, opret_witness, reading_operation, TestContract, TestReader,
+    };
+    use crate::{GenesisSeals, OperationSeals, RgbSealDef};
+
+    #[allow(clippy::result_large_err)]
+    fn update(
+        channel: &mut Channel,
+        ledger: &impl ContractApi<TxoSeal>,
+        txid: Txid,
+        nonce: u64,
+    ) -> Result<Opid, ChannelError> {
+        let genesis_addr = CellAddr::new(issue().genesis_opid(), 0);
+        let mut operation = reading_operation(genesis_addr);
+        operation.nonce = nonce.into();
+        operation.immutable_out = small_vec![StateData::new(0u64, nonce)];
+        let opid = operation.opid();
+        let genesis = GenesisSeals { genesis: genesis(), defined_seals: none!() };
+        let reader = TestReader::new(vec![OperationSeals { operation, defined_seals: none!(), witness: None }])
+            .with_genesis(genesis);
+        channel.update(ledger, txid, reader, &default!())?;
+        Ok(opid)
+    }
+
+    #[test]
+    fn commitments() {
+        let mut ledger = contract();
+        ledger
+            .global
+            .insert(CellAddr::new(issue().genesis_opid(), 0), genesis().immutable_out[0].value);
+        let known_ops = ledger.known_ops.clone();
+        let mut channel = Channel::new();
+
+        let first = Txid::from_byte_array([1; 32]);
+        let second = Txid::from_byte_array([2; 32]);
+        let first_opid = update(&mut channel, &ledger, first, 1).unwrap();
+        assert_eq!(channel.current(), Some(first));
+        assert!(channel
+            .current_commitment(&ledger)
+            .unwrap()
+            .operations()
+            .contains_key(&first_opid));
+        assert_eq!(ledger.known_ops, known_ops);
+
+        let second_opid = update(&mut channel, &ledger, second, 2).unwrap();
+        assert_eq!(channel.current(), Some(second));
+        assert!(channel.commitment(first).is_some());
+
+        let revoked = channel.revoke(first).unwrap();
+        assert!(revoked.operations().contains_key(&first_opid));
+        assert!(channel.commitment(first).is_none());
+        assert_eq!(channel.current(), Some(second));
+        let commitment = channel.current_commitment(&ledger).unwrap();
+        assert!(commitment.operations().contains_key(&second_opid));
+        assert!(matches!(update(&mut channel, &ledger, first, 3), Err(ChannelError::Revoked(txid)) if txid == first));
+        assert_eq!(ledger.known_ops, known_ops);
+
+        // The ledger evolves while the channel is open: the published operation leaves the channel
+        let published = commitment.operations()[&second_opid].clone();
+        ledger.known_ops.insert(second_opid, published);
+        let commitment = channel.current_commitment(&ledger).unwrap();
+        assert!(commitment.operations().is_empty());
+        assert!(commitment.witnesses().is_empty());
+        assert!(channel
+            .commitment(second)
+            .unwrap()
+            .operations()
+            .contains_key(&second_opid));
+    }
+
+    #[test]
+    fn witnessed() {
+        let outpoint = Outpoint::new(Txid::from_byte_array([1; 32]), 0);
+        let seal = WTxoSeal {
+            primary: WOutpoint::Extern(outpoint),
+            secondary: TxoSealExt::Noise(Noise::from(Bytes::from_byte_array([0u8; 40]))),
+        };
+        let mut genesis = genesis();
+        genesis.destructible_out[0].auth = seal.auth_token();
+        let ledger: TestContract = contract_with(genesis.clone());
+        let known_ops = ledger.known_ops.clone();
+        let mut channel = Channel::new();
+
+        let mut operation = operation();
+        operation.contract_id = ledger.contract_id;
+        operation.destructible_in[0].addr = CellAddr::new(genesis.opid(ledger.contract_id), 0);
+        let opid = operation.opid();
+        let witness = opret_witness(outpoint, opid);
+        let txid = witness.published.pub_id();
+        let reader = || {
+            TestReader::new(vec![OperationSeals {
+                operation: operation.clone(),
+                defined_seals: none!(),
+                witness: Some(witness.clone()),
+            }])
+            .with_genesis(GenesisSeals {
+                genesis: genesis.clone(),
+                defined_seals: small_bmap! { 0 => seal },
+            })
+        };
+
+        channel
+            .update(&ledger, txid, reader(), &default!())
+            .unwrap();
+        let commitment = channel.current_commitment(&ledger).unwrap();
+        assert!(commitment.operations().contains_key(&opid));
+        assert_eq!(commitment.witnesses()[&opid].published.pub_id(), txid);
+
+        let other = Txid::from_byte_array([2; 32]);
+        let err = channel
+            .update(&ledger, other, reader(), &default!())
+            .unwrap_err();
+        assert!(matches!(err, ChannelError::ForeignWitness { expected, found } if expected == other && found == txid));
+        assert!(channel.commitment(other).is_none());
+        assert_eq!(channel.current(), Some(txid));
+        assert_eq!(ledger.known_ops, known_ops);
+    }
+}
@@ -0,0 +1,423 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 LNP/BP Laboratories,
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2025 RGB Consortium, Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Key-signature single-use seals, allowing to run RGB contracts without a blockchain.
+//!
+//! A [`KeySeal`] is a public key of the seal owner with a nonce, bound to a federation which
+//! maintains a set of spent seals. A seal is closed by the owner signing the message (an
+//! operation id) with the seal key; the federation then adds the seal to its spent-set and signs
+//! the spending record with its key. The owner signatures form the client-side witness
+//! ([`KeyAnchor`]), and the federation signature is the published witness ([`KeySignature`]).
+//!
+//! The federation is trusted to never sign two different spending records for the same seal;
+//! this guarantee is not a part of the seal closing verification.
+
+use core::error::Error;
+use core::num::ParseIntError;
+use core::str::FromStr;
+
+use amplify::confinement::{SmallOrdMap, SmallOrdSet};
+use amplify::{hex, ByteArray, Bytes32, Bytes64};
+use commit_verify::{CommitEncode, CommitId, CommitmentId, DigestExt, Sha256};
+use secp256k1::{schnorr, Keypair, XOnlyPublicKey, SECP256K1};
+use single_use_seals::{ClientSideWitness, PublishedWitness, SealWitness, SingleUseSeal};
+use ultrasonic::AuthToken;
+
+use crate::{AuthTokenVersion, RgbSeal, RgbSealDef, LIB_NAME_RGB};
+
+/// Tag used for the messages signed by the seal owners.
+pub const KEY_SEAL_SIG_TAG: &str = "urn:lnp-bp:rgb:key-seal-sig#2025-06-04";
+
+/// Key-signature single-use seal.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{key}/{nonce}@{federation}")]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = KeySealId)]
+pub struct KeySeal {
+    /// X-only public key of the federation maintaining the spent-set for the seal.
+    pub federation: Bytes32,
+    /// X-only public key of the seal owner.
+    pub key: Bytes32,
+    /// Nonce distinguishing seals of the same owner.
+    pub nonce: u64,
+}
+
+impl KeySeal {
+    /// Constructs a seal owned by the `key` under the spent-set of the `federation`.
+    pub fn new(federation: XOnlyPublicKey, key: XOnlyPublicKey, nonce: u64) -> Self {
+        Self {
+            federation: Bytes32::from_byte_array(federation.serialize()),
+            key: Bytes32::from_byte_array(key.serialize()),
+            nonce,
+        }
+    }
+
+    /// Computes the message which has to be signed by the seal owner to close the seal over the
+    /// `message`.
+    pub fn sig_hash(&self, message: Bytes32) -> [u8; 32] {
+        let mut engine = Sha256::from_tag(KEY_SEAL_SIG_TAG);
+        engine.input_raw(&self.commit_id().to_byte_array());
+        engine.input_raw(&message.to_byte_array());
+        engine.finish()
+    }
+}
+
+impl FromStr for KeySeal {
+    type Err = KeySealParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, rest) = s
+            .split_once('/')
+            .ok_or_else(|| KeySealParseError::Format(s.to_owned()))?;
+        let (nonce, federation) = rest
+            .split_once('@')
+            .ok_or_else(|| KeySealParseError::Format(s.to_owned()))?;
+        Ok(KeySeal {
+            federation: federation.parse()?,
+            key: key.parse()?,
+            nonce: nonce.parse()?,
+        })
+    }
+}
+
+/// Commitment to a [`KeySeal`], used to produce seal [`AuthToken`]s.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct KeySealId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitmentId for KeySealId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:key-seal#2025-06-04";
+}
+
+impl From<Sha256> for KeySealId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Spending record, which is added by the federation to its spent-set.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = KeySpendId)]
+pub struct KeySpend {
+    /// Seals spent by the record.
+    pub seals: SmallOrdSet<KeySeal>,
+    /// Message over which the seals are closed.
+    pub message: Bytes32,
+}
+
+/// Identifier of a [`KeySpend`] record, serving as the seal witness id.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct KeySpendId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitmentId for KeySpendId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:key-spend#2025-06-04";
+}
+
+impl From<Sha256> for KeySpendId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Client-side witness for the key-signature seals, containing signatures of the seal owners.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct KeyAnchor {
+    /// BIP-340 signatures of the closed seal owners (see [`KeySeal::sig_hash`]).
+    pub signatures: SmallOrdMap<KeySeal, Bytes64>,
+}
+
+impl KeyAnchor {
+    /// Signs the closing of the `seal` over the `message` with the seal owner `keypair`.
+    ///
+    /// # Panics
+    ///
+    /// If the anchor already contains the maximum number of signatures.
+    pub fn sign(&mut self, seal: KeySeal, message: Bytes32, keypair: &Keypair) {
+        let sig = SECP256K1.sign_schnorr_no_aux_rand(&seal.sig_hash(message), keypair);
+        self.signatures
+            .insert(seal, Bytes64::from_byte_array(sig.to_byte_array()))
+            .expect("too many signatures");
+    }
+}
+
+/// Federation signature of a spending record, serving as a published witness for [`KeySeal`].
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct KeySignature {
+    /// X-only public key of the federation.
+    pub federation: Bytes32,
+    /// Id of the signed spending record.
+    pub spend: KeySpendId,
+    /// BIP-340 signature of the federation over the spending record id.
+    pub signature: Bytes64,
+}
+
+impl KeySignature {
+    /// Signs the spending record with the federation `keypair`.
+    pub fn sign(spend: KeySpendId, keypair: &Keypair) -> Self {
+        let sig = SECP256K1.sign_schnorr_no_aux_rand(spend.as_slice(), keypair);
+        Self {
+            federation: Bytes32::from_byte_array(keypair.x_only_public_key().0.serialize()),
+            spend,
+            signature: Bytes64::from_byte_array(sig.to_byte_array()),
+        }
+    }
+}
+
+impl SingleUseSeal for KeySeal {
+    type Message = Bytes32;
+    type PubWitness = KeySignature;
+    type CliWitness = KeyAnchor;
+
+    fn is_included(&self, _message: Self::Message, witness: &SealWitness<Self>) -> bool {
+        witness.published.federation == self.federation && witness.client.signatures.contains_key(self)
+    }
+}
+
+impl PublishedWitness<KeySeal> for KeySignature {
+    type PubId = KeySpendId;
+    type Error = KeySignatureError;
+
+    fn pub_id(&self) -> KeySpendId { self.spend }
+
+    fn verify_commitment(&self, proof: KeySpendId) -> Result<(), Self::Error> {
+        if self.spend != proof {
+            return Err(KeySignatureError::SpendMismatch);
+        }
+        let federation = XOnlyPublicKey::from_byte_array(&self.federation.to_byte_array())
+            .map_err(|_| KeySignatureError::InvalidKey(self.federation))?;
+        let sig = schnorr::Signature::from_byte_array(self.signature.to_byte_array());
+        SECP256K1
+            .verify_schnorr(&sig, self.spend.as_slice(), &federation)
+            .map_err(|_| KeySignatureError::InvalidSignature)
+    }
+}
+
+impl ClientSideWitness for KeyAnchor {
+    type Seal = KeySeal;
+    type Proof = KeySpendId;
+    type Error = KeyAnchorError;
+
+    fn convolve_commit(&self, msg: Bytes32) -> Result<KeySpendId, Self::Error> {
+        for (seal, signature) in &self.signatures {
+            let key = XOnlyPublicKey::from_byte_array(&seal.key.to_byte_array())
+                .map_err(|_| KeyAnchorError::InvalidKey(*seal))?;
+            let sig = schnorr::Signature::from_byte_array(signature.to_byte_array());
+            SECP256K1
+                .verify_schnorr(&sig, &seal.sig_hash(msg), &key)
+                .map_err(|_| KeyAnchorError::InvalidSignature(*seal))?;
+        }
+        let seals = SmallOrdSet::from_iter_checked(self.signatures.keys().copied());
+        Ok(KeySpend { seals, message: msg }.commit_id())
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), impl Error>
+    where Self: Sized {
+        if *self != other {
+            return Err(KeyAnchorError::Mismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Errors verifying key-signature seal closing with a provided federation signature.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum KeySignatureError {
+    /// the federation signature is given for a different spending record.
+    SpendMismatch,
+
+    /// invalid federation public key {0}.
+    InvalidKey(Bytes32),
+
+    /// invalid federation signature.
+    InvalidSignature,
+}
+
+/// Errors parsing key-signature seals.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum KeySealParseError {
+    /// key seal '{0}' doesn't match the '<key>/<nonce>@<federation>' form.
+    Format(String),
+
+    /// invalid public key in a key seal. Details: {0}
+    #[from]
+    Key(hex::Error),
+
+    /// invalid nonce in a key seal. Details: {0}
+    #[from]
+    Nonce(ParseIntError),
+}
+
+/// Errors in the client-side witness of key-signature seals.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum KeyAnchorError {
+    /// invalid public key of the seal {0}.
+    InvalidKey(KeySeal),
+
+    /// invalid owner signature for the seal {0}.
+    InvalidSignature(KeySeal),
+
+    /// anchors for the same spending record do not match.
+    Mismatch,
+}
+
+impl RgbSeal for KeySeal {
+    type Definition = KeySeal;
+    type Published = KeySignature;
+    type Client = KeyAnchor;
+    type WitnessId = KeySpendId;
+}
+
+/// Key-signature seals are never defined relatively to a witness, thus a seal serves as its own
+/// definition.
+impl RgbSealDef for KeySeal {
+    type Src = KeySeal;
+    type ParseError = KeySealParseError;
+
+    fn auth_token(&self) -> AuthToken { AuthTokenVersion::V0.derive(self.commit_id().to_byte_array()) }
+
+    fn parse(s: &str) -> Result<Self, KeySealParseError> { s.parse() }
+
+    fn resolve(&self, _witness_id: KeySpendId) -> KeySeal { *self }
+
+    fn to_src(&self) -> Option<KeySeal> { Some(*self) }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use secp256k1::SecretKey;
+    use single_use_seals::SealError;
+
+    use super::*;
+
+    fn keypair(no: u8) -> Keypair {
+        let sk = SecretKey::from_byte_array(&[no; 32]).unwrap();
+        Keypair::from_secret_key(SECP256K1, &sk)
+    }
+
+    fn setup() -> (Bytes32, KeySeal, SealWitness<KeySeal>) {
+        let federation = keypair(1);
+        let owner = keypair(2);
+        let seal = KeySeal::new(federation.x_only_public_key().0, owner.x_only_public_key().0, 0);
+        let msg = Bytes32::from_byte_array([0x4D; 32]);
+
+        let mut anchor = KeyAnchor::default();
+        anchor.sign(seal, msg, &owner);
+        let spend = KeySpend { seals: small_bset![seal], message: msg }.commit_id();
+        let signature = KeySignature::sign(spend, &federation);
+        (msg, seal, SealWitness::new(signature, anchor))
+    }
+
+    #[test]
+    fn seal_closing() {
+        let (msg, seal, witness) = setup();
+        assert!(seal.is_included(msg, &witness));
+        witness.verify_seal_closing(seal, msg).unwrap();
+
+        let other = Bytes32::from_byte_array([0x4E; 32]);
+        assert!(matches!(
+            witness.verify_seal_closing(seal, other),
+            Err(SealError::Client(KeyAnchorError::InvalidSignature(s))) if s == seal
+        ));
+
+        let unrelated = KeySeal { nonce: 1, ..seal };
+        assert!(matches!(witness.verify_seal_closing(unrelated, msg), Err(SealError::NotIncluded(..))));
+
+        let foreign = KeySeal { federation: seal.key, ..seal };
+        assert!(!foreign.is_included(msg, &witness));
+    }
+
+    #[test]
+    fn owner_signature() {
+        let (msg, seal, mut witness) = setup();
+        witness.client.sign(seal, msg, &keypair(3));
+        assert!(matches!(
+            witness.verify_seal_closing(seal, msg),
+            Err(SealError::Client(KeyAnchorError::InvalidSignature(_)))
+        ));
+    }
+
+    #[test]
+    fn federation_signature() {
+        let (msg, seal, mut witness) = setup();
+        let spend = witness.published.spend;
+        let mut forged = KeySignature::sign(spend, &keypair(3));
+        forged.federation = witness.published.federation;
+        witness.published = forged;
+        assert!(matches!(
+            witness.verify_seal_closing(seal, msg),
+            Err(SealError::Published(KeySignatureError::InvalidSignature))
+        ));
+
+        witness.published = KeySignature::sign(KeySpendId::from_byte_array([0; 32]), &keypair(1));
+        assert!(matches!(
+            witness.verify_seal_closing(seal, msg),
+            Err(SealError::Published(KeySignatureError::SpendMismatch))
+        ));
+    }
+
+    #[test]
+    fn definition() {
+        let (_, seal, witness) = setup();
+        assert_eq!(seal.to_src(), Some(seal));
+        assert_eq!(seal.resolve(witness.published.pub_id()), seal);
+        assert!(seal.is_closable(&witness.published));
+        assert_ne!(seal.auth_token(), KeySeal { nonce: 1, ..seal }.auth_token());
+    }
+
+    #[test]
+    fn parse() {
+        let (_, seal, _) = setup();
+        for nonce in [0, 1, u64::MAX] {
+            let seal = KeySeal { nonce, ..seal };
+            assert_eq!(KeySeal::parse(&seal.to_string()), Ok(seal));
+        }
+        let s = seal.to_string();
+        assert!(matches!(KeySeal::parse(&s.replace('@', "")), Err(KeySealParseError::Format(_))));
+        assert!(matches!(KeySeal::parse(&s.replace("/0@", "/x@")), Err(KeySealParseError::Nonce(_))));
+        assert!(matches!(KeySeal::parse(&s[2..]), Err(KeySealParseError::Key(_))));
+    }
+}
@@ -0,0 +1,620 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 LNP/BP Laboratories,
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2025 RGB Consortium, Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Conversion of the legacy (v0.11 and earlier) RGB consignment data.
+//!
+//! The module provides a data model for the constructs of the legacy consignments (schema-based
+//! operations, transition bundles, anchors, blinded seals, valencies and state extensions) and a
+//! procedure converting them into the current [`Operation`], [`GenesisSeals`] and
+//! [`OperationSeals`] model.
+//!
+//! # Nota bene
+//!
+//! Decoding of the legacy consignment archives is out of the scope of this library: the data
+//! model must be filled in by the tools able to decode the legacy data.
+//!
+//! The conversion produces a new contract: the converted operations have new ids, and the legacy
+//! witness transactions do not commit to them. Thus, the legacy anchors are never carried over and
+//! are reported as [`Unrepresentable::Anchor`]; the converted operations spending seals must be
+//! re-anchored with [`Conversion::reanchor`] before the history can pass
+//! [`crate::ContractVerify::evaluate`].
+
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::vec::Vec;
+
+use amplify::confinement::{SmallBlob, SmallOrdMap, SmallVec};
+use amplify::Bytes32;
+use bp::seals::{Noise, TxoSeal, TxoSealExt, WOutpoint, WTxoSeal};
+use bp::{Outpoint, Txid, Vout};
+use commit_verify::{DigestExt, ReservedBytes, Sha256};
+use single_use_seals::SealWitness;
+use strict_encoding::StrictDumb;
+use ultrasonic::{
+    fe256, CallId, CellAddr, Codex, ContractMeta, Genesis, Input, Issue, Operation, Opid, RawData, StateCell,
+    StateData, StateValue,
+};
+
+use crate::{GenesisSeals, OperationSeals, RgbSealDef};
+
+/// Tag used for deriving noise of the converted seal definitions from the legacy seal blinding
+/// factors.
+pub const LEGACY_SEAL_NOISE_TAG: &str = "urn:lnp-bp:rgb:legacy-seal#2025-06-01";
+
+/// Identifier of a legacy operation (genesis, state transition or state extension).
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[display(LowerHex)]
+pub struct LegacyOpid(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+/// Legacy operation output, referenced by the legacy state transition inputs.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{op}/{ty}/{no}")]
+pub struct LegacyOpout {
+    /// Id of the operation defining the output.
+    pub op: LegacyOpid,
+    /// Assignment type.
+    pub ty: u16,
+    /// Number of the assignment within the assignments of the same type.
+    pub no: u16,
+}
+
+/// Legacy single-use seal definition.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum LegacySeal {
+    /// Revealed seal definition (legacy `GraphSeal`).
+    Revealed {
+        /// Id of the transaction containing the seal output; `None` if the seal is defined
+        /// relatively to the witness transaction.
+        txid: Option<Txid>,
+        /// Transaction output number.
+        vout: Vout,
+        /// Blinding factor.
+        blinding: u64,
+    },
+
+    /// Blinded seal definition (legacy `SecretSeal`).
+    Blinded(Bytes32),
+}
+
+/// State of a legacy assignment.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum LegacyState {
+    /// Declarative (void) state.
+    Void,
+    /// Revealed fungible amount.
+    Fungible(u64),
+    /// Confidential (Pedersen-committed) fungible amount.
+    ConfidentialFungible,
+    /// Structured data.
+    Structured(SmallBlob),
+    /// Attachment.
+    Attachment(Bytes32),
+}
+
+/// Legacy assignment of an owned state to a single-use seal.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LegacyAssignment {
+    /// Assignment type.
+    pub ty: u16,
+    /// Seal definition.
+    pub seal: LegacySeal,
+    /// Assigned state.
+    pub state: LegacyState,
+}
+
+/// Legacy global state item.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LegacyGlobal {
+    /// Global state type.
+    pub ty: u16,
+    /// Strict-serialized state data.
+    pub data: SmallBlob,
+}
+
+/// Legacy contract genesis.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LegacyGenesis {
+    /// Id of the legacy genesis operation.
+    pub opid: LegacyOpid,
+    /// Global state defined by the genesis.
+    pub globals: Vec<LegacyGlobal>,
+    /// Owned state defined by the genesis.
+    pub assignments: Vec<LegacyAssignment>,
+    /// Valencies defined by the genesis.
+    pub valencies: BTreeSet<u16>,
+}
+
+/// Legacy state transition.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LegacyTransition {
+    /// Id of the legacy state transition.
+    pub opid: LegacyOpid,
+    /// Transition type.
+    pub transition_type: u16,
+    /// Transition nonce.
+    pub nonce: u64,
+    /// Global state defined by the transition.
+    pub globals: Vec<LegacyGlobal>,
+    /// Outputs of previous operations spent by the transition.
+    pub inputs: Vec<LegacyOpout>,
+    /// Owned state defined by the transition.
+    pub assignments: Vec<LegacyAssignment>,
+    /// Valencies defined by the transition.
+    pub valencies: BTreeSet<u16>,
+}
+
+/// Legacy state extension.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LegacyExtension {
+    /// Id of the legacy state extension.
+    pub opid: LegacyOpid,
+    /// Valencies redeemed by the extension.
+    pub redeemed: BTreeMap<u16, LegacyOpid>,
+}
+
+/// Legacy consignment.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LegacyConsignment {
+    /// Contract genesis.
+    pub genesis: LegacyGenesis,
+    /// State transitions from all transition bundles, in the topological order.
+    pub transitions: Vec<LegacyTransition>,
+    /// State extensions.
+    pub extensions: Vec<LegacyExtension>,
+    /// Legacy operations which were anchored to a witness transaction.
+    pub anchored: BTreeSet<LegacyOpid>,
+}
+
+/// Parameters of the conversion of a legacy consignment into a new contract.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LegacyImport {
+    /// Metadata of the new contract.
+    pub meta: ContractMeta,
+    /// Codex of the new contract.
+    pub codex: Codex,
+    /// Contract method used by the converted genesis.
+    pub genesis_call: CallId,
+    /// Mapping of the legacy transition types to the codex contract methods.
+    ///
+    /// Transition types missing in the map are mapped to the method with the same number.
+    pub calls: BTreeMap<u16, CallId>,
+}
+
+/// Legacy constructs which cannot be represented in the current data model.
+#[derive(Clone, Eq, PartialEq, Debug, Display)]
+#[display(doc_comments)]
+pub enum Unrepresentable {
+    /// operation {0} defines valencies {1:?}, which are removed since v0.12.
+    Valencies(LegacyOpid, BTreeSet<u16>),
+
+    /// state extension {0} is skipped, since extensions are removed since v0.12.
+    Extension(LegacyOpid),
+
+    /// output {0} is skipped, since it is assigned to a blinded seal, which can't be converted into
+    /// an auth token.
+    BlindedSeal(LegacyOpout),
+
+    /// output {0} is skipped, since its state is confidential.
+    ConfidentialState(LegacyOpout),
+
+    /// output {0} is skipped, since the owned state data doesn't fit a field element.
+    OwnedData(LegacyOpout),
+
+    /// anchor of operation {0} is dropped, since its witness doesn't commit to the converted
+    /// operation.
+    Anchor(LegacyOpid),
+}
+
+/// Result of a legacy consignment conversion.
+#[derive(Clone)]
+pub struct Conversion {
+    /// New contract issue.
+    pub issue: Issue,
+    /// Seals defined by the converted genesis.
+    pub genesis_seals: SmallOrdMap<u16, WTxoSeal>,
+    /// Converted operations following the genesis, in a form of a consignment stream.
+    pub operations: Vec<OperationSeals<TxoSeal>>,
+    /// Mapping of legacy operation ids to the ids of the converted operations.
+    pub opids: BTreeMap<LegacyOpid, Opid>,
+    /// Legacy constructs which were not converted.
+    pub unrepresentable: Vec<Unrepresentable>,
+}
+
+/// Errors making conversion of a legacy consignment impossible.
+#[derive(Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum LegacyError {
+    /// operation {0} has too many inputs or outputs to be represented.
+    Confinement(LegacyOpid),
+
+    /// operation {0} spends output {1}, which is unknown or was not converted.
+    UnresolvedInput(LegacyOpid, LegacyOpout),
+
+    /// legacy operation {0} is not a converted state transition.
+    UnknownOperation(LegacyOpid),
+}
+
+impl Conversion {
+    /// Returns the converted genesis with its seals, which must be provided as the first record of
+    /// the consignment stream (see [`crate::stream::ReadOperationExt::with_genesis`]).
+    pub fn genesis(&self) -> GenesisSeals<TxoSeal> {
+        GenesisSeals {
+            genesis: self.issue.genesis.clone(),
+            defined_seals: self.genesis_seals.clone(),
+        }
+    }
+
+    /// Re-anchors a converted state transition, providing a new `witness` committing to the id of
+    /// the converted operation.
+    ///
+    /// Errors if the legacy operation is not a converted state transition.
+    pub fn reanchor(&mut self, legacy: LegacyOpid, witness: SealWitness<TxoSeal>) -> Result<(), LegacyError> {
+        let opid = self
+            .opids
+            .get(&legacy)
+            .ok_or(LegacyError::UnknownOperation(legacy))?;
+        let op = self
+            .operations
+            .iter_mut()
+            .find(|op| op.operation.opid() == *opid)
+            .ok_or(LegacyError::UnknownOperation(legacy))?;
+        op.witness = Some(witness);
+        Ok(())
+    }
+}
+
+impl LegacyImport {
+    /// Converts a legacy consignment into a new contract.
+    pub fn convert(&self, consignment: &LegacyConsignment) -> Result<Conversion, LegacyError> {
+        let mut conv = Converter { outputs: none!(), unrepresentable: none!() };
+
+        // Genesis
+        let legacy = &consignment.genesis;
+        let (destructible_out, defined_seals, positions) = conv.outputs(legacy.opid, &legacy.assignments)?;
+        let mut genesis = Genesis::strict_dumb();
+        genesis.codex_id = self.codex.codex_id();
+        genesis.call_id = self.genesis_call;
+        genesis.destructible_out = destructible_out;
+        genesis.immutable_out = globals(legacy.opid, &legacy.globals)?;
+        conv.valencies(legacy.opid, &legacy.valencies);
+
+        let issue = Issue {
+            version: ReservedBytes::default(),
+            meta: self.meta.clone(),
+            codex: self.codex.clone(),
+            genesis,
+        };
+        let contract_id = issue.contract_id();
+        let genesis_opid = issue.genesis_opid();
+        conv.register(genesis_opid, positions);
+
+        let mut operations = vec![];
+        let mut opids = bmap! { legacy.opid => genesis_opid };
+
+        // State transitions
+        for legacy in &consignment.transitions {
+            let mut destructible_in = Vec::with_capacity(legacy.inputs.len());
+            for prevout in &legacy.inputs {
+                let addr = conv
+                    .outputs
+                    .get(prevout)
+                    .ok_or(LegacyError::UnresolvedInput(legacy.opid, *prevout))?;
+                destructible_in.push(Input { addr: *addr, witness: StateValue::None });
+            }
+            let (destructible_out, defined_seals, positions) = conv.outputs(legacy.opid, &legacy.assignments)?;
+            conv.valencies(legacy.opid, &legacy.valencies);
+
+            let operation = Operation {
+                version: default!(),
+                contract_id,
+                call_id: self
+                    .calls
+                    .get(&legacy.transition_type)
+                    .copied()
+                    .unwrap_or(legacy.transition_type),
+                nonce: fe256::from(legacy.nonce),
+                witness: StateValue::None,
+                destructible_in: SmallVec::try_from(destructible_in)
+                    .map_err(|_| LegacyError::Confinement(legacy.opid))?,
+                immutable_in: none!(),
+                destructible_out,
+                immutable_out: globals(legacy.opid, &legacy.globals)?,
+            };
+            let opid = operation.opid();
+            conv.register(opid, positions);
+            opids.insert(legacy.opid, opid);
+            operations.push(OperationSeals { operation, defined_seals, witness: None });
+        }
+
+        conv.unrepresentable.extend(
+            consignment
+                .extensions
+                .iter()
+                .map(|ext| Unrepresentable::Extension(ext.opid)),
+        );
+        conv.unrepresentable.extend(
+            consignment
+                .anchored
+                .iter()
+                .copied()
+                .map(Unrepresentable::Anchor),
+        );
+
+        Ok(Conversion {
+            issue,
+            genesis_seals: defined_seals,
+            operations,
+            opids,
+            unrepresentable: conv.unrepresentable,
+        })
+    }
+}
+
+impl LegacySeal {
+    /// Converts a revealed legacy seal into a seal definition.
+    ///
+    /// The legacy blinding factor is used as a nonce for the seal definition noise. Returns `None`
+    /// for the blinded seals.
+    pub fn to_definition(&self) -> Option<WTxoSeal> {
+        let LegacySeal::Revealed { txid, vout, blinding } = *self else {
+            return None;
+        };
+        let primary = match txid {
+            None => WOutpoint::Wout(vout),
+            Some(txid) => WOutpoint::Extern(Outpoint::new(txid, vout)),
+        };
+        let engine = Sha256::from_tag(LEGACY_SEAL_NOISE_TAG);
+        Some(WTxoSeal {
+            primary,
+            secondary: TxoSealExt::Noise(Noise::with(primary, engine, blinding)),
+        })
+    }
+}
+
+struct Converter {
+    outputs: BTreeMap<LegacyOpout, CellAddr>,
+    unrepresentable: Vec<Unrepresentable>,
+}
+
+impl Converter {
+    #[allow(clippy::type_complexity)]
+    fn outputs(
+        &mut self,
+        opid: LegacyOpid,
+        assignments: &[LegacyAssignment],
+    ) -> Result<(SmallVec<StateCell>, SmallOrdMap<u16, WTxoSeal>, Vec<(LegacyOpout, u16)>), LegacyError> {
+        let mut cells = Vec::new();
+        let mut positions = Vec::new();
+        let mut seals = SmallOrdMap::new();
+        let mut counters = BTreeMap::<u16, u16>::new();
+        for assignment in assignments {
+            let counter = counters.entry(assignment.ty).or_default();
+            let opout = LegacyOpout { op: opid, ty: assignment.ty, no: *counter };
+            *counter += 1;
+
+            let data = match &assignment.state {
+                LegacyState::Void => StateValue::Single { first: fe256::from(assignment.ty) },
+                LegacyState::Fungible(amount) => StateValue::new(assignment.ty, *amount),
+                LegacyState::ConfidentialFungible => {
+                    self.unrepresentable
+                        .push(Unrepresentable::ConfidentialState(opout));
+                    continue;
+                }
+                LegacyState::Structured(_) | LegacyState::Attachment(_) => {
+                    self.unrepresentable.push(Unrepresentable::OwnedData(opout));
+                    continue;
+                }
+            };
+            let Some(seal) = assignment.seal.to_definition() else {
+                self.unrepresentable
+                    .push(Unrepresentable::BlindedSeal(opout));
+                continue;
+            };
+
+            let pos = u16::try_from(cells.len()).map_err(|_| LegacyError::Confinement(opid))?;
+            cells.push(StateCell { data, auth: seal.auth_token(), lock: None });
+            seals
+                .insert(pos, seal)
+                .map_err(|_| LegacyError::Confinement(opid))?;
+            positions.push((opout, pos));
+        }
+        let cells = SmallVec::try_from(cells).map_err(|_| LegacyError::Confinement(opid))?;
+        Ok((cells, seals, positions))
+    }
+
+    /// Registers converted outputs once the id of the converted operation is known.
+    fn register(&mut self, opid: Opid, positions: Vec<(LegacyOpout, u16)>) {
+        self.outputs.extend(
+            positions
+                .into_iter()
+                .map(|(opout, pos)| (opout, CellAddr::new(opid, pos))),
+        );
+    }
+
+    fn valencies(&mut self, opid: LegacyOpid, valencies: &BTreeSet<u16>) {
+        if !valencies.is_empty() {
+            self.unrepresentable
+                .push(Unrepresentable::Valencies(opid, valencies.clone()));
+        }
+    }
+}
+
+fn globals(opid: LegacyOpid, globals: &[LegacyGlobal]) -> Result<SmallVec<StateData>, LegacyError> {
+    let data = globals
+        .iter()
+        .map(|global| StateData {
+            value: StateValue::Single { first: fe256::from(global.ty) },
+            raw: Some(RawData::from(global.data.clone())),
+        })
+        .collect::<Vec<_>>();
+    SmallVec::try_from(data).map_err(|_| LegacyError::Confinement(opid))
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use amplify::ByteArray;
+
+    use super::*;
+
+    fn seal(vout: u32) -> LegacySeal {
+        LegacySeal::Revealed { txid: None, vout: Vout::from_u32(vout), blinding: 0xDEAD }
+    }
+
+    fn consignment() -> LegacyConsignment {
+        let genesis = LegacyGenesis {
+            opid: LegacyOpid::from([1u8; 32]),
+            globals: vec![LegacyGlobal { ty: 2000, data: small_blob![0xCA, 0xFE] }],
+            assignments: vec![
+                LegacyAssignment { ty: 4000, seal: seal(0), state: LegacyState::Fungible(100) },
+                LegacyAssignment {
+                    ty: 4000,
+                    seal: LegacySeal::Blinded(Bytes32::from_byte_array([7u8; 32])),
+                    state: LegacyState::Fungible(5),
+                },
+                LegacyAssignment { ty: 4001, seal: seal(1), state: LegacyState::Void },
+            ],
+            valencies: none!(),
+        };
+        let transition = LegacyTransition {
+            opid: LegacyOpid::from([2u8; 32]),
+            transition_type: 10000,
+            nonce: 1,
+            globals: vec![],
+            inputs: vec![LegacyOpout { op: genesis.opid, ty: 4000, no: 0 }],
+            assignments: vec![
+                LegacyAssignment {
+                    ty: 4000,
+                    seal: seal(0),
+                    state: LegacyState::ConfidentialFungible,
+                },
+                LegacyAssignment { ty: 4000, seal: seal(1), state: LegacyState::Fungible(105) },
+            ],
+            valencies: bset![1],
+        };
+        LegacyConsignment {
+            genesis,
+            anchored: bset![transition.opid],
+            transitions: vec![transition],
+            extensions: vec![LegacyExtension {
+                opid: LegacyOpid::from([3u8; 32]),
+                redeemed: bmap! { 1 => LegacyOpid::from([2u8; 32]) },
+            }],
+        }
+    }
+
+    fn import() -> LegacyImport {
+        LegacyImport {
+            meta: ContractMeta::strict_dumb(),
+            codex: Codex::strict_dumb(),
+            genesis_call: 0,
+            calls: bmap! { 10000 => 1 },
+        }
+    }
+
+    #[test]
+    fn convert() {
+        let consignment = consignment();
+        let conv = import().convert(&consignment).unwrap();
+
+        assert_eq!(conv.operations.len(), 1);
+        let genesis = conv.genesis();
+        assert_eq!(genesis.genesis.codex_id, conv.issue.codex_id());
+        assert_eq!(genesis.genesis.destructible_out.len(), 2);
+        assert_eq!(genesis.defined_seals.len(), 2);
+        assert_eq!(genesis.genesis.immutable_out.len(), 1);
+
+        let genesis_opid = conv.issue.genesis_opid();
+        let transition = &conv.operations[0];
+        assert_eq!(transition.operation.contract_id, conv.issue.contract_id());
+        assert_eq!(transition.operation.call_id, 1);
+        assert_eq!(transition.operation.destructible_in.len(), 1);
+        assert_eq!(transition.operation.destructible_in[0].addr, CellAddr::new(genesis_opid, 0));
+        assert_eq!(transition.operation.destructible_out.len(), 1);
+        assert_eq!(transition.operation.destructible_out[0].data, StateValue::new(4000u16, 105u64));
+        for (pos, seal) in &transition.defined_seals {
+            assert_eq!(transition.operation.destructible_out[*pos as usize].auth, seal.auth_token());
+        }
+
+        assert_eq!(conv.opids, bmap! {
+            consignment.genesis.opid => genesis_opid,
+            consignment.transitions[0].opid => transition.operation.opid()
+        });
+
+        let genesis_opid = consignment.genesis.opid;
+        let transition_opid = consignment.transitions[0].opid;
+        assert_eq!(conv.unrepresentable, vec![
+            Unrepresentable::BlindedSeal(LegacyOpout { op: genesis_opid, ty: 4000, no: 1 }),
+            Unrepresentable::ConfidentialState(LegacyOpout { op: transition_opid, ty: 4000, no: 0 }),
+            Unrepresentable::Valencies(transition_opid, bset![1]),
+            Unrepresentable::Extension(consignment.extensions[0].opid),
+            Unrepresentable::Anchor(transition_opid),
+        ]);
+    }
+
+    #[test]
+    fn unresolved_input() {
+        let mut consignment = consignment();
+        let blinded = LegacyOpout { op: consignment.genesis.opid, ty: 4000, no: 1 };
+        consignment.transitions[0].inputs.push(blinded);
+        assert_eq!(
+            import().convert(&consignment).err(),
+            Some(LegacyError::UnresolvedInput(consignment.transitions[0].opid, blinded))
+        );
+    }
+
+    #[test]
+    fn reanchor() {
+        let consignment = consignment();
+        let mut conv = import().convert(&consignment).unwrap();
+        assert_eq!(conv.operations[0].witness, None);
+
+        let witness = SealWitness::<TxoSeal>::new(strict_dumb!(), strict_dumb!());
+        conv.reanchor(consignment.transitions[0].opid, witness.clone())
+            .unwrap();
+        assert_eq!(conv.operations[0].witness, Some(witness.clone()));
+
+        let genesis_opid = consignment.genesis.opid;
+        assert_eq!(conv.reanchor(genesis_opid, witness).unwrap_err(), LegacyError::UnknownOperation(genesis_opid));
+    }
+
+    #[test]
+    fn seal_definition() {
+        let txid = Txid::from_byte_array([0xAD; 32]);
+        let LegacySeal::Revealed { vout, blinding, .. } = seal(3) else {
+            unreachable!()
+        };
+        let def = LegacySeal::Revealed { txid: Some(txid), vout, blinding }
+            .to_definition()
+            .unwrap();
+        assert_eq!(def.primary, WOutpoint::Extern(Outpoint::new(txid, vout)));
+        assert_ne!(seal(3).to_definition(), seal(4).to_definition());
+        assert_eq!(LegacySeal::Blinded(Bytes32::from_byte_array([7u8; 32])).to_definition(), None);
+    }
+}
@@ -57,10 +57,31 @@ extern crate core;
 
 mod verify;
 mod seals;
+pub mod stream;
+pub mod multi;
+#[cfg(feature = "bitcoin")]
+pub mod channel;
+#[cfg(feature = "liquid")]
+pub mod liquid;
+#[cfg(feature = "prime")]
+pub mod prime;
+#[cfg(feature = "keysig")]
+pub mod keysig;
+#[cfg(feature = "legacy")]
+pub mod legacy;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod mock;
 
-pub use seals::{RgbSeal, RgbSealDef};
+#[cfg(feature = "bitcoin")]
+pub use seals::bitcoin::{SealBlinding, SEAL_BLINDING_TAG};
+#[cfg(any(feature = "bitcoin", feature = "liquid"))]
+pub use seals::txo::TxoSealParseError;
+pub use seals::{AuthTokenVersion, CommitmentMethod, RgbSeal, RgbSealDef, WitnessCommitment};
 pub use single_use_seals::*;
-pub use verify::{ContractApi, ContractVerify, OperationSeals, ReadOperation, VerificationError};
+pub use verify::{
+    ContractApi, ContractVerify, EvaluationReport, GenesisError, GenesisSeals, OperationSeals, QuarantineReason,
+    Quarantined, ReadOperation, StreamItem, VerificationError, VerifyConfig, WitnessResolver, WitnessStatus,
+};
 
 /// Strict type library name for all RGB-related types.
 pub const LIB_NAME_RGB: &str = "RGB";
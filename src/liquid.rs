@@ -0,0 +1,988 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 LNP/BP Laboratories,
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2025 RGB Consortium, Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Single-use seals for the Liquid sidechain.
+//!
+//! Liquid transactions are not compatible with bitcoin ones: their outputs carry confidential
+//! amounts, asset tags and nonces, and inputs may contain asset issuances and peg-ins. Thus, Liquid
+//! seals use their own [`LiquidTx`] published witness, serialized and identified according to the
+//! Elements consensus rules.
+//!
+//! Seals are closed with opret commitments only: the witness transaction must contain an
+//! `OP_RETURN` output committing to the multiprotocol commitment produced from a [`LiquidAnchor`].
+
+use core::error::Error;
+use core::str::FromStr;
+use std::io::{self, Cursor, Read, Write};
+
+use amplify::{ByteArray, Bytes, Bytes32, IoError};
+use bp::seals::{mmb, mpc, AnchorError, AnchorMergeError, TxoSealExt, WOutpoint};
+use bp::{
+    ConsensusDecode, ConsensusDecodeError, ConsensusEncode, LockTime, Outpoint, ScriptPubkey, SeqNo, SigScript, TxVer,
+    Txid, VarIntArray, VarIntBytes, Vout, Witness,
+};
+use commit_verify::{CommitEncode, CommitId, CommitmentId, DigestExt, Sha256};
+use single_use_seals::{ClientSideWitness, PublishedWitness, SealWitness, SingleUseSeal};
+use strict_encoding::StrictDumb;
+use ultrasonic::AuthToken;
+
+use crate::seals::txo::parse_txo_def;
+use crate::{
+    AuthTokenVersion, CommitmentMethod, RgbSeal, RgbSealDef, TxoSealParseError, WitnessCommitment, LIB_NAME_RGB,
+};
+
+/// Flag in the output number of a transaction input outpoint, signalling an asset issuance.
+pub const OUTPOINT_ISSUANCE_FLAG: u32 = 1 << 31;
+/// Flag in the output number of a transaction input outpoint, signalling a peg-in.
+pub const OUTPOINT_PEGIN_FLAG: u32 = 1 << 30;
+/// Mask for the output number of a transaction input outpoint, removing the flags.
+pub const OUTPOINT_INDEX_MASK: u32 = 0x3FFF_FFFF;
+
+const PREFIX_NULL: u8 = 0x00;
+const PREFIX_EXPLICIT: u8 = 0x01;
+
+/// Amount, which may be either explicit or blinded with a Pedersen commitment.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::Null)]
+pub enum ConfidentialValue {
+    /// The amount is not provided.
+    #[strict_type(tag = 0)]
+    Null,
+
+    /// Explicit amount in satoshis.
+    #[strict_type(tag = 1)]
+    Explicit(u64),
+
+    /// Pedersen commitment to the amount, including the prefix byte (`0x08` or `0x09`).
+    #[strict_type(tag = 2)]
+    Confidential(Bytes<33>),
+}
+
+/// Asset tag, which may be either an explicit asset id or a blinded generator.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::Null)]
+pub enum ConfidentialAsset {
+    /// The asset is not provided.
+    #[strict_type(tag = 0)]
+    Null,
+
+    /// Explicit asset id.
+    #[strict_type(tag = 1)]
+    Explicit(Bytes32),
+
+    /// Blinded asset generator, including the prefix byte (`0x0a` or `0x0b`).
+    #[strict_type(tag = 2)]
+    Confidential(Bytes<33>),
+}
+
+/// Nonce used for the output blinding, which may be either explicit or an ECDH public key.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::Null)]
+pub enum ConfidentialNonce {
+    /// The nonce is not provided.
+    #[strict_type(tag = 0)]
+    Null,
+
+    /// Explicit nonce value.
+    #[strict_type(tag = 1)]
+    Explicit(Bytes32),
+
+    /// Compressed ECDH public key, including the prefix byte (`0x02` or `0x03`).
+    #[strict_type(tag = 2)]
+    Confidential(Bytes<33>),
+}
+
+/// Asset issuance or reissuance performed by a Liquid transaction input.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct AssetIssuance {
+    /// Blinding nonce of the reissued asset; zero for a new asset issuance.
+    pub asset_blinding_nonce: Bytes32,
+    /// Contract hash for a new asset issuance, or the asset entropy for a reissuance.
+    pub asset_entropy: Bytes32,
+    /// Amount of the issued asset.
+    pub amount: ConfidentialValue,
+    /// Amount of the reissuance tokens.
+    pub inflation_keys: ConfidentialValue,
+}
+
+/// Witness data of a Liquid transaction input.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LiquidTxInWitness {
+    /// Range proof for the issued amount.
+    pub amount_rangeproof: VarIntBytes,
+    /// Range proof for the issued reissuance tokens.
+    pub inflation_keys_rangeproof: VarIntBytes,
+    /// Script witness stack.
+    pub script_witness: Witness,
+    /// Peg-in witness stack.
+    pub pegin_witness: Witness,
+}
+
+impl LiquidTxInWitness {
+    /// Detects whether the witness doesn't contain any data.
+    pub fn is_empty(&self) -> bool {
+        self.amount_rangeproof.is_empty()
+            && self.inflation_keys_rangeproof.is_empty()
+            && self.script_witness.is_empty()
+            && self.pegin_witness.is_empty()
+    }
+}
+
+/// Liquid transaction input.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LiquidTxIn {
+    /// The spent output.
+    pub prev_output: Outpoint,
+    /// Whether the input is a peg-in from the bitcoin blockchain.
+    pub is_pegin: bool,
+    /// Signature script.
+    pub sig_script: SigScript,
+    /// Input sequence number.
+    pub sequence: SeqNo,
+    /// Asset issuance done by the input.
+    pub asset_issuance: Option<AssetIssuance>,
+    /// Witness data for the input.
+    pub witness: LiquidTxInWitness,
+}
+
+/// Witness data of a Liquid transaction output.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LiquidTxOutWitness {
+    /// Asset surjection proof.
+    pub surjection_proof: VarIntBytes,
+    /// Amount range proof.
+    pub rangeproof: VarIntBytes,
+}
+
+impl LiquidTxOutWitness {
+    /// Detects whether the witness doesn't contain any data.
+    pub fn is_empty(&self) -> bool { self.surjection_proof.is_empty() && self.rangeproof.is_empty() }
+}
+
+/// Liquid transaction output.
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LiquidTxOut {
+    /// Asset of the output.
+    pub asset: ConfidentialAsset,
+    /// Amount of the output.
+    pub value: ConfidentialValue,
+    /// Nonce used for the output blinding.
+    pub nonce: ConfidentialNonce,
+    /// Output script; empty for the fee outputs.
+    pub script_pubkey: ScriptPubkey,
+    /// Witness data for the output.
+    pub witness: LiquidTxOutWitness,
+}
+
+impl LiquidTxOut {
+    /// Detects whether the output is a transaction fee output.
+    pub fn is_fee(&self) -> bool {
+        self.script_pubkey.is_empty() && matches!(self.value, ConfidentialValue::Explicit(_))
+    }
+}
+
+/// Liquid transaction, serving as a published witness for [`LiquidSeal`].
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LiquidTx {
+    /// Transaction version.
+    pub version: TxVer,
+    /// Transaction inputs.
+    pub inputs: VarIntArray<LiquidTxIn>,
+    /// Transaction outputs.
+    pub outputs: VarIntArray<LiquidTxOut>,
+    /// Transaction lock time.
+    pub lock_time: LockTime,
+}
+
+impl LiquidTx {
+    /// Detects whether the transaction has any witness data.
+    pub fn has_witness(&self) -> bool {
+        self.inputs.iter().any(|input| !input.witness.is_empty())
+            || self.outputs.iter().any(|output| !output.witness.is_empty())
+    }
+
+    /// Computes the transaction id, which commits to the transaction without the witness data.
+    pub fn txid(&self) -> Txid {
+        let mut enc = Sha256::default();
+        self.encode(&mut enc, false).expect("engines don't error");
+        let mut double = Sha256::default();
+        double.input_raw(&enc.finish());
+        Txid::from_byte_array(double.finish())
+    }
+
+    /// Deserializes the transaction from the Elements consensus serialization.
+    pub fn consensus_deserialize(bytes: impl AsRef<[u8]>) -> Result<Self, LiquidDecodeError> {
+        let mut cursor = Cursor::new(bytes.as_ref());
+        let me = Self::consensus_decode(&mut cursor)?;
+        if cursor.position() as usize != bytes.as_ref().len() {
+            return Err(LiquidDecodeError::DataNotConsumed);
+        }
+        Ok(me)
+    }
+
+    /// Decodes the transaction from the Elements consensus serialization.
+    pub fn consensus_decode(reader: &mut impl Read) -> Result<Self, LiquidDecodeError> {
+        let version = TxVer::consensus_decode(reader)?;
+        let flags = u8::consensus_decode(reader)?;
+        if flags > 1 {
+            return Err(LiquidDecodeError::WitnessFlag(flags));
+        }
+        let mut inputs = decode_array(reader, decode_input)?;
+        let mut outputs = decode_array(reader, decode_output)?;
+        let lock_time = LockTime::consensus_decode(reader)?;
+        if flags == 1 {
+            for input in &mut inputs {
+                input.witness = LiquidTxInWitness {
+                    amount_rangeproof: VarIntBytes::consensus_decode(reader)?,
+                    inflation_keys_rangeproof: VarIntBytes::consensus_decode(reader)?,
+                    script_witness: Witness::consensus_decode(reader)?,
+                    pegin_witness: Witness::consensus_decode(reader)?,
+                };
+            }
+            for output in &mut outputs {
+                output.witness = LiquidTxOutWitness {
+                    surjection_proof: VarIntBytes::consensus_decode(reader)?,
+                    rangeproof: VarIntBytes::consensus_decode(reader)?,
+                };
+            }
+        }
+        Ok(Self { version, inputs, outputs, lock_time })
+    }
+
+    fn encode(&self, writer: &mut impl Write, witness: bool) -> Result<usize, IoError> {
+        let witness = witness && self.has_witness();
+        let mut counter = self.version.consensus_encode(writer)?;
+        counter += (witness as u8).consensus_encode(writer)?;
+        counter += encode_array(writer, &self.inputs, encode_input)?;
+        counter += encode_array(writer, &self.outputs, encode_output)?;
+        counter += self.lock_time.consensus_encode(writer)?;
+        if witness {
+            for input in &self.inputs {
+                counter += input.witness.amount_rangeproof.consensus_encode(writer)?;
+                counter += input
+                    .witness
+                    .inflation_keys_rangeproof
+                    .consensus_encode(writer)?;
+                counter += input.witness.script_witness.consensus_encode(writer)?;
+                counter += input.witness.pegin_witness.consensus_encode(writer)?;
+            }
+            for output in &self.outputs {
+                counter += output.witness.surjection_proof.consensus_encode(writer)?;
+                counter += output.witness.rangeproof.consensus_encode(writer)?;
+            }
+        }
+        Ok(counter)
+    }
+}
+
+impl ConsensusEncode for LiquidTx {
+    fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> { self.encode(writer, true) }
+}
+
+fn encode_array<T>(
+    writer: &mut impl Write,
+    items: &VarIntArray<T>,
+    f: impl Fn(&mut dyn Write, &T) -> Result<usize, IoError>,
+) -> Result<usize, IoError> {
+    let mut counter = bp::VarInt::with(items.len()).consensus_encode(writer)?;
+    for item in items {
+        counter += f(writer, item)?;
+    }
+    Ok(counter)
+}
+
+fn decode_array<T>(
+    reader: &mut impl Read,
+    f: impl Fn(&mut dyn Read) -> Result<T, LiquidDecodeError>,
+) -> Result<VarIntArray<T>, LiquidDecodeError> {
+    let len = bp::VarInt::consensus_decode(reader)?;
+    let mut items = Vec::new();
+    for _ in 0..len.to_u64() {
+        items.push(f(reader)?);
+    }
+    Ok(VarIntArray::try_from(items).map_err(ConsensusDecodeError::from)?)
+}
+
+fn encode_input(mut writer: &mut dyn Write, input: &LiquidTxIn) -> Result<usize, IoError> {
+    let mut vout = input.prev_output.vout.to_u32();
+    if vout != u32::MAX {
+        if input.asset_issuance.is_some() {
+            vout |= OUTPOINT_ISSUANCE_FLAG;
+        }
+        if input.is_pegin {
+            vout |= OUTPOINT_PEGIN_FLAG;
+        }
+    }
+    let mut counter = input.prev_output.txid.consensus_encode(&mut writer)?;
+    counter += vout.consensus_encode(&mut writer)?;
+    counter += input.sig_script.consensus_encode(&mut writer)?;
+    counter += input.sequence.consensus_encode(&mut writer)?;
+    if let Some(issuance) = &input.asset_issuance {
+        counter += issuance
+            .asset_blinding_nonce
+            .consensus_encode(&mut writer)?;
+        counter += issuance.asset_entropy.consensus_encode(&mut writer)?;
+        counter += issuance.amount.consensus_encode(&mut writer)?;
+        counter += issuance.inflation_keys.consensus_encode(&mut writer)?;
+    }
+    Ok(counter)
+}
+
+fn decode_input(mut reader: &mut dyn Read) -> Result<LiquidTxIn, LiquidDecodeError> {
+    let txid = Txid::consensus_decode(&mut reader)?;
+    let mut vout = u32::consensus_decode(&mut reader)?;
+    let (mut has_issuance, mut is_pegin) = (false, false);
+    if vout != u32::MAX {
+        has_issuance = vout & OUTPOINT_ISSUANCE_FLAG != 0;
+        is_pegin = vout & OUTPOINT_PEGIN_FLAG != 0;
+        vout &= OUTPOINT_INDEX_MASK;
+    }
+    let sig_script = SigScript::consensus_decode(&mut reader)?;
+    let sequence = SeqNo::consensus_decode(&mut reader)?;
+    let asset_issuance = if has_issuance {
+        Some(AssetIssuance {
+            asset_blinding_nonce: Bytes32::from(<[u8; 32]>::consensus_decode(&mut reader)?),
+            asset_entropy: Bytes32::from(<[u8; 32]>::consensus_decode(&mut reader)?),
+            amount: ConfidentialValue::consensus_decode(&mut reader)?,
+            inflation_keys: ConfidentialValue::consensus_decode(&mut reader)?,
+        })
+    } else {
+        None
+    };
+    Ok(LiquidTxIn {
+        prev_output: Outpoint::new(txid, Vout::from_u32(vout)),
+        is_pegin,
+        sig_script,
+        sequence,
+        asset_issuance,
+        witness: none!(),
+    })
+}
+
+fn encode_output(mut writer: &mut dyn Write, output: &LiquidTxOut) -> Result<usize, IoError> {
+    let mut counter = output.asset.consensus_encode(&mut writer)?;
+    counter += output.value.consensus_encode(&mut writer)?;
+    counter += output.nonce.consensus_encode(&mut writer)?;
+    counter += output.script_pubkey.consensus_encode(&mut writer)?;
+    Ok(counter)
+}
+
+fn decode_output(mut reader: &mut dyn Read) -> Result<LiquidTxOut, LiquidDecodeError> {
+    Ok(LiquidTxOut {
+        asset: ConfidentialAsset::consensus_decode(&mut reader)?,
+        value: ConfidentialValue::consensus_decode(&mut reader)?,
+        nonce: ConfidentialNonce::consensus_decode(&mut reader)?,
+        script_pubkey: ScriptPubkey::consensus_decode(&mut reader)?,
+        witness: none!(),
+    })
+}
+
+fn read_commitment(prefix: u8, reader: &mut impl Read) -> Result<Bytes<33>, IoError> {
+    let mut buf = [0u8; 33];
+    buf[0] = prefix;
+    reader.read_exact(&mut buf[1..])?;
+    Ok(buf.into())
+}
+
+impl ConfidentialValue {
+    /// Encodes the value according to the Elements consensus rules.
+    pub fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
+        match self {
+            Self::Null => PREFIX_NULL.consensus_encode(writer),
+            Self::Explicit(value) => {
+                writer.write_all(&[PREFIX_EXPLICIT])?;
+                writer.write_all(&value.to_be_bytes())?;
+                Ok(9)
+            }
+            Self::Confidential(commitment) => {
+                writer.write_all(commitment.as_slice())?;
+                Ok(33)
+            }
+        }
+    }
+
+    /// Decodes the value according to the Elements consensus rules.
+    pub fn consensus_decode(reader: &mut impl Read) -> Result<Self, LiquidDecodeError> {
+        match u8::consensus_decode(reader)? {
+            PREFIX_NULL => Ok(Self::Null),
+            PREFIX_EXPLICIT => {
+                let mut buf = [0u8; 8];
+                reader.read_exact(&mut buf).map_err(IoError::from)?;
+                Ok(Self::Explicit(u64::from_be_bytes(buf)))
+            }
+            prefix @ (0x08 | 0x09) => Ok(Self::Confidential(read_commitment(prefix, reader)?)),
+            prefix => Err(LiquidDecodeError::ValuePrefix(prefix)),
+        }
+    }
+}
+
+impl ConfidentialAsset {
+    /// Encodes the asset according to the Elements consensus rules.
+    pub fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
+        match self {
+            Self::Null => PREFIX_NULL.consensus_encode(writer),
+            Self::Explicit(id) => {
+                writer.write_all(&[PREFIX_EXPLICIT])?;
+                writer.write_all(id.as_slice())?;
+                Ok(33)
+            }
+            Self::Confidential(generator) => {
+                writer.write_all(generator.as_slice())?;
+                Ok(33)
+            }
+        }
+    }
+
+    /// Decodes the asset according to the Elements consensus rules.
+    pub fn consensus_decode(reader: &mut impl Read) -> Result<Self, LiquidDecodeError> {
+        match u8::consensus_decode(reader)? {
+            PREFIX_NULL => Ok(Self::Null),
+            PREFIX_EXPLICIT => Ok(Self::Explicit(Bytes32::from(<[u8; 32]>::consensus_decode(reader)?))),
+            prefix @ (0x0A | 0x0B) => Ok(Self::Confidential(read_commitment(prefix, reader)?)),
+            prefix => Err(LiquidDecodeError::AssetPrefix(prefix)),
+        }
+    }
+}
+
+impl ConfidentialNonce {
+    /// Encodes the nonce according to the Elements consensus rules.
+    pub fn consensus_encode(&self, writer: &mut impl Write) -> Result<usize, IoError> {
+        match self {
+            Self::Null => PREFIX_NULL.consensus_encode(writer),
+            Self::Explicit(nonce) => {
+                writer.write_all(&[PREFIX_EXPLICIT])?;
+                writer.write_all(nonce.as_slice())?;
+                Ok(33)
+            }
+            Self::Confidential(key) => {
+                writer.write_all(key.as_slice())?;
+                Ok(33)
+            }
+        }
+    }
+
+    /// Decodes the nonce according to the Elements consensus rules.
+    pub fn consensus_decode(reader: &mut impl Read) -> Result<Self, LiquidDecodeError> {
+        match u8::consensus_decode(reader)? {
+            PREFIX_NULL => Ok(Self::Null),
+            PREFIX_EXPLICIT => Ok(Self::Explicit(Bytes32::from(<[u8; 32]>::consensus_decode(reader)?))),
+            prefix @ (0x02 | 0x03) => Ok(Self::Confidential(read_commitment(prefix, reader)?)),
+            prefix => Err(LiquidDecodeError::NoncePrefix(prefix)),
+        }
+    }
+}
+
+/// Errors decoding Liquid transactions.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum LiquidDecodeError {
+    /// {0}
+    #[from]
+    #[from(IoError)]
+    #[from(io::Error)]
+    Consensus(ConsensusDecodeError),
+
+    /// unsupported transaction flags {0:#04x}.
+    WitnessFlag(u8),
+
+    /// invalid prefix {0:#04x} of a confidential value.
+    ValuePrefix(u8),
+
+    /// invalid prefix {0:#04x} of a confidential asset.
+    AssetPrefix(u8),
+
+    /// invalid prefix {0:#04x} of a confidential nonce.
+    NoncePrefix(u8),
+
+    /// transaction data are followed by some excessive bytes.
+    DataNotConsumed,
+}
+
+/// The Liquid TxO-based single-use seal protocol (see [`SingleUseSeal`]).
+///
+/// Unlike [`WLiquidSeal`], this seal always contains information about the defined seal.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display("{primary}/{secondary}")]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LiquidSeal {
+    /// A primary seal definition.
+    pub primary: Outpoint,
+    /// A fallback seal definition.
+    pub secondary: TxoSealExt,
+}
+
+/// A Liquid single-use seal definition, which may point to the output of a witness transaction
+/// committing to the message defining the seal.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display("{primary}/{secondary}")]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = LiquidSealId)]
+pub struct WLiquidSeal {
+    /// A primary seal definition.
+    pub primary: WOutpoint,
+    /// A fallback seal definition.
+    pub secondary: TxoSealExt,
+}
+
+impl FromStr for WLiquidSeal {
+    type Err = TxoSealParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (primary, secondary) = parse_txo_def(s)?;
+        Ok(WLiquidSeal { primary, secondary })
+    }
+}
+
+/// Commitment to a [`WLiquidSeal`], which is used to produce seal [`AuthToken`]s distinct from
+/// the ones of the bitcoin seals.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LiquidSealId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitmentId for LiquidSealId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:liquid-seal#2025-06-04";
+}
+
+impl From<Sha256> for LiquidSealId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Client-side witness for the Liquid TxO-based seals.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct LiquidAnchor {
+    /// The proof that each witness transaction input is used only in a single bundle.
+    pub mmb_proof: mmb::BundleProof,
+    /// The protocol under which the client-side witness is valid.
+    pub mpc_protocol: mpc::ProtocolId,
+    /// The inclusion proof of a commitment under the [`Self::mpc_protocol`] into the published
+    /// witness.
+    pub mpc_proof: mpc::MerkleProof,
+}
+
+impl LiquidAnchor {
+    /// Verify that the bundle proof matches the witness transaction structure.
+    pub fn verify_bundle(&self, seal: Outpoint, msg: mmb::Message, tx: &LiquidTx) -> bool {
+        let Some(input_index) = tx.inputs.iter().position(|input| input.prev_output == seal) else {
+            return false;
+        };
+        let Ok(input_index) = u32::try_from(input_index) else {
+            return false;
+        };
+        self.mmb_proof.map.get(&input_index) == Some(&msg)
+    }
+}
+
+impl SingleUseSeal for LiquidSeal {
+    type Message = mmb::Message;
+    type PubWitness = LiquidTx;
+    type CliWitness = LiquidAnchor;
+
+    fn is_included(&self, message: Self::Message, witness: &SealWitness<Self>) -> bool {
+        // Fallback proofs are not supported, thus only the primary seal can be closed.
+        witness
+            .client
+            .verify_bundle(self.primary, message, &witness.published)
+    }
+}
+
+impl PublishedWitness<LiquidSeal> for LiquidTx {
+    type PubId = Txid;
+    type Error = LiquidSealError;
+
+    fn pub_id(&self) -> Txid { self.txid() }
+
+    fn verify_commitment(&self, proof: mpc::Commitment) -> Result<(), Self::Error> {
+        let out = self
+            .outputs
+            .iter()
+            .find(|out| out.script_pubkey.is_op_return())
+            .ok_or(LiquidSealError::NoOutput)?;
+        if out.script_pubkey != ScriptPubkey::op_return(proof.as_slice()) {
+            return Err(LiquidSealError::InvalidCommitment);
+        }
+        Ok(())
+    }
+}
+
+impl ClientSideWitness for LiquidAnchor {
+    type Proof = mpc::Commitment;
+    type Seal = LiquidSeal;
+    type Error = AnchorError;
+
+    fn convolve_commit(&self, mmb_message: mmb::Message) -> Result<mpc::Commitment, Self::Error> {
+        if self.mmb_proof.map.values().all(|msg| *msg != mmb_message) {
+            return Err(AnchorError::Mmb(mmb_message));
+        }
+        let bundle_id = self.mmb_proof.commit_id();
+        let mpc_message = mpc::Message::from_byte_array(bundle_id.to_byte_array());
+        let mpc_commit = self.mpc_proof.convolve(self.mpc_protocol, mpc_message)?;
+        Ok(mpc_commit)
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), impl Error>
+    where Self: Sized {
+        if *self != other {
+            return Err(AnchorMergeError::AnchorMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Errors verifying Liquid single-use seal closing with a provided witness.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum LiquidSealError {
+    /// witness transaction contains no OP_RETURN output.
+    NoOutput,
+
+    /// the first OP_RETURN output of the witness transaction doesn't contain a valid commitment.
+    InvalidCommitment,
+}
+
+impl RgbSeal for LiquidSeal {
+    type Definition = WLiquidSeal;
+    type Published = LiquidTx;
+    type Client = LiquidAnchor;
+    type WitnessId = Txid;
+
+    /// Liquid witnesses always commit in their first `OP_RETURN` output.
+    fn commitment(witness: &SealWitness<Self>) -> Option<WitnessCommitment> {
+        let output = witness
+            .published
+            .outputs
+            .iter()
+            .position(|out| out.script_pubkey.is_op_return())?;
+        Some(WitnessCommitment { method: CommitmentMethod::Opret, output: output as u32 })
+    }
+}
+
+impl RgbSealDef for WLiquidSeal {
+    type Src = LiquidSeal;
+    type ParseError = TxoSealParseError;
+
+    fn auth_token(&self) -> AuthToken { AuthTokenVersion::V0.derive(self.commit_id().to_byte_array()) }
+
+    fn parse(s: &str) -> Result<Self, TxoSealParseError> { s.parse() }
+
+    fn resolve(&self, witness_id: Txid) -> LiquidSeal {
+        let primary = match self.primary {
+            WOutpoint::Wout(wout) => Outpoint::new(witness_id, wout),
+            WOutpoint::Extern(outpoint) => outpoint,
+        };
+        LiquidSeal { primary, secondary: self.secondary }
+    }
+
+    fn to_src(&self) -> Option<LiquidSeal> {
+        let primary = match self.primary {
+            WOutpoint::Wout(_) => return None,
+            WOutpoint::Extern(outpoint) => outpoint,
+        };
+        Some(LiquidSeal { primary, secondary: self.secondary })
+    }
+
+    fn is_closable(&self, witness: &LiquidTx) -> bool {
+        match self.primary {
+            WOutpoint::Wout(wout) => witness
+                .outputs
+                .get(wout.to_u32() as usize)
+                .is_some_and(|out| !out.script_pubkey.is_op_return() && !out.is_fee()),
+            WOutpoint::Extern(_) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use amplify::confinement::{Confined, SmallOrdMap};
+    use amplify::num::u5;
+    use bp::seals::mpc::{MessageMap, MessageSource};
+    use bp::seals::Noise;
+    use single_use_seals::SealError;
+
+    use super::*;
+
+    fn input(no: u8) -> LiquidTxIn {
+        LiquidTxIn {
+            prev_output: Outpoint::new(Txid::from_byte_array([no; 32]), no as u32),
+            is_pegin: false,
+            sig_script: none!(),
+            sequence: SeqNo::ZERO,
+            asset_issuance: None,
+            witness: none!(),
+        }
+    }
+
+    fn output(value: ConfidentialValue, script_pubkey: ScriptPubkey) -> LiquidTxOut {
+        LiquidTxOut {
+            asset: ConfidentialAsset::Explicit(Bytes32::from_byte_array([0xA5; 32])),
+            value,
+            nonce: ConfidentialNonce::Null,
+            script_pubkey,
+            witness: none!(),
+        }
+    }
+
+    fn tx() -> LiquidTx {
+        let mut issuing = input(1);
+        issuing.asset_issuance = Some(AssetIssuance {
+            asset_blinding_nonce: Bytes32::from_byte_array([0; 32]),
+            asset_entropy: Bytes32::from_byte_array([0xE7; 32]),
+            amount: ConfidentialValue::Explicit(21_000_000),
+            inflation_keys: ConfidentialValue::Null,
+        });
+        issuing.witness.amount_rangeproof = Confined::from_checked(vec![0xAA; 8]);
+        let mut pegin = input(2);
+        pegin.is_pegin = true;
+        pegin.witness.pegin_witness = Witness::from_consensus_stack([vec![0xBB; 4]]);
+        let mut coinbase = input(3);
+        coinbase.prev_output = Outpoint::coinbase();
+
+        let mut confidential = output(
+            ConfidentialValue::Confidential(Bytes::from_byte_array([0x08; 33])),
+            ScriptPubkey::from_checked(vec![0x51]),
+        );
+        confidential.asset = ConfidentialAsset::Confidential(Bytes::from_byte_array([0x0A; 33]));
+        confidential.nonce = ConfidentialNonce::Confidential(Bytes::from_byte_array([0x02; 33]));
+        confidential.witness = LiquidTxOutWitness {
+            surjection_proof: Confined::from_checked(vec![0xCC; 16]),
+            rangeproof: Confined::from_checked(vec![0xDD; 32]),
+        };
+        let fee = output(ConfidentialValue::Explicit(250), ScriptPubkey::new());
+
+        LiquidTx {
+            version: TxVer::V2,
+            inputs: Confined::from_checked(vec![issuing, pegin, coinbase]),
+            outputs: Confined::from_checked(vec![confidential, fee]),
+            lock_time: LockTime::ZERO,
+        }
+    }
+
+    #[test]
+    fn consensus_roundtrip() {
+        let tx = tx();
+        assert!(tx.has_witness());
+        let data = tx.consensus_serialize();
+        assert_eq!(data[4], 1);
+        assert_eq!(LiquidTx::consensus_deserialize(&data).unwrap(), tx);
+
+        let mut stripped = tx.clone();
+        stripped
+            .inputs
+            .iter_mut()
+            .for_each(|input| input.witness = none!());
+        stripped
+            .outputs
+            .iter_mut()
+            .for_each(|output| output.witness = none!());
+        let data = stripped.consensus_serialize();
+        assert_eq!(data[4], 0);
+        assert_eq!(LiquidTx::consensus_deserialize(&data).unwrap(), stripped);
+        assert_eq!(stripped.txid(), tx.txid());
+
+        let mut data = tx.consensus_serialize();
+        data.push(0);
+        assert_eq!(LiquidTx::consensus_deserialize(&data), Err(LiquidDecodeError::DataNotConsumed));
+    }
+
+    #[test]
+    fn outpoint_flags() {
+        let tx = tx();
+        let data = tx.consensus_serialize();
+        // version, flags, number of inputs and the previous txid
+        let vout = u32::from_le_bytes(data[38..42].try_into().unwrap());
+        assert_eq!(vout, 1 | OUTPOINT_ISSUANCE_FLAG);
+
+        let data = LiquidTx {
+            inputs: Confined::from_checked(vec![tx.inputs[1].clone()]),
+            ..tx
+        }
+        .consensus_serialize();
+        let vout = u32::from_le_bytes(data[38..42].try_into().unwrap());
+        assert_eq!(vout, 2 | OUTPOINT_PEGIN_FLAG);
+    }
+
+    #[test]
+    fn txid() {
+        let tx = tx();
+        let mut changed = tx.clone();
+        changed.outputs[1].value = ConfidentialValue::Explicit(251);
+        assert_ne!(changed.txid(), tx.txid());
+        let mut changed = tx.clone();
+        changed.inputs[1].is_pegin = false;
+        assert_ne!(changed.txid(), tx.txid());
+    }
+
+    #[test]
+    fn invalid_prefixes() {
+        let mut tx = tx();
+        tx.outputs[0].value = ConfidentialValue::Confidential(Bytes::from_byte_array([0x07; 33]));
+        let data = tx.consensus_serialize();
+        assert_eq!(LiquidTx::consensus_deserialize(data), Err(LiquidDecodeError::ValuePrefix(0x07)));
+
+        tx.outputs[0].asset = ConfidentialAsset::Confidential(Bytes::from_byte_array([0x08; 33]));
+        let data = tx.consensus_serialize();
+        assert_eq!(LiquidTx::consensus_deserialize(data), Err(LiquidDecodeError::AssetPrefix(0x08)));
+
+        tx.outputs[0].asset = ConfidentialAsset::Null;
+        tx.outputs[0].value = ConfidentialValue::Null;
+        tx.outputs[0].nonce = ConfidentialNonce::Confidential(Bytes::from_byte_array([0x04; 33]));
+        let data = tx.consensus_serialize();
+        assert_eq!(LiquidTx::consensus_deserialize(data), Err(LiquidDecodeError::NoncePrefix(0x04)));
+
+        let mut data = tx.consensus_serialize();
+        data[4] = 2;
+        assert_eq!(LiquidTx::consensus_deserialize(data), Err(LiquidDecodeError::WitnessFlag(2)));
+    }
+
+    fn setup() -> (mmb::Message, LiquidSeal, SealWitness<LiquidSeal>) {
+        let msg = mmb::Message::from_byte_array([0x4D; 32]);
+        let bundle = mmb::BundleProof { map: SmallOrdMap::from_checked(bmap! { 1 => msg }) };
+        let seal = LiquidSeal {
+            primary: input(2).prev_output,
+            secondary: TxoSealExt::Noise(Noise::from(Bytes::from_byte_array([0u8; 40]))),
+        };
+
+        let protocol = mpc::ProtocolId::from_byte_array([0xADu8; 32]);
+        let source = mpc::Source {
+            min_depth: u5::with(3),
+            entropy: 0xFE,
+            messages: MessageMap::from(Confined::from_checked(
+                bmap! { protocol => MessageSource::Mmb(bundle.clone()) },
+            )),
+        };
+        let merkle_tree = source.into_merkle_tree().unwrap();
+        let (_, mpc_proof) = merkle_tree.clone().into_proofs().next().unwrap();
+        let anchor = LiquidAnchor { mmb_proof: bundle, mpc_protocol: protocol, mpc_proof };
+
+        let mut tx = tx();
+        let commitment =
+            output(ConfidentialValue::Explicit(0), ScriptPubkey::op_return(merkle_tree.commit_id().as_slice()));
+        tx.outputs.push(commitment).unwrap();
+        (msg, seal, SealWitness::new(tx, anchor))
+    }
+
+    #[test]
+    fn seal_closing() {
+        let (msg, seal, witness) = setup();
+        assert!(seal.is_included(msg, &witness));
+        witness.verify_seal_closing(seal, msg).unwrap();
+
+        let other = mmb::Message::from_byte_array([0x4E; 32]);
+        assert!(!seal.is_included(other, &witness));
+        assert!(matches!(witness.verify_seal_closing(seal, other), Err(SealError::NotIncluded(..))));
+
+        let unrelated = LiquidSeal { primary: input(5).prev_output, ..seal };
+        assert!(matches!(witness.verify_seal_closing(unrelated, msg), Err(SealError::NotIncluded(..))));
+    }
+
+    #[test]
+    fn seal_commitment() {
+        let (msg, seal, mut witness) = setup();
+        let last = witness.published.outputs.len() - 1;
+        witness.published.outputs[last].script_pubkey = ScriptPubkey::op_return(&[0u8; 32]);
+        assert!(matches!(
+            witness.verify_seal_closing(seal, msg),
+            Err(SealError::Published(LiquidSealError::InvalidCommitment))
+        ));
+
+        witness.published.outputs.remove(last).unwrap();
+        assert!(matches!(witness.verify_seal_closing(seal, msg), Err(SealError::Published(LiquidSealError::NoOutput))));
+    }
+
+    #[test]
+    fn seal_definitions() {
+        let secondary = TxoSealExt::Fallback(Outpoint::coinbase());
+        let tx = tx();
+        let txid = tx.txid();
+
+        let wout = WLiquidSeal { primary: WOutpoint::Wout(Vout::from_u32(0)), secondary };
+        assert_eq!(wout.to_src(), None);
+        assert_eq!(wout.resolve(txid), LiquidSeal { primary: Outpoint::new(txid, 0), secondary });
+        assert!(wout.is_closable(&tx));
+        // Fee outputs can't be spent
+        assert!(!WLiquidSeal { primary: WOutpoint::Wout(Vout::from_u32(1)), secondary }.is_closable(&tx));
+        assert!(!WLiquidSeal { primary: WOutpoint::Wout(Vout::from_u32(2)), secondary }.is_closable(&tx));
+
+        let outpoint = Outpoint::new(Txid::from_byte_array([0xEE; 32]), 1);
+        let ext = WLiquidSeal { primary: WOutpoint::Extern(outpoint), secondary };
+        let seal = LiquidSeal { primary: outpoint, secondary };
+        assert_eq!(ext.to_src(), Some(seal));
+        assert_eq!(ext.resolve(txid), seal);
+        assert!(ext.is_closable(&tx));
+        assert_ne!(ext.auth_token(), wout.auth_token());
+    }
+
+    #[test]
+    fn seal_definition_parse() {
+        let outpoint = Outpoint::new(Txid::from_byte_array([0xEE; 32]), 1);
+        let noise = Noise::with(WOutpoint::Extern(outpoint), Sha256::from_tag("test"), 0);
+        for primary in [WOutpoint::Wout(Vout::from_u32(3)), WOutpoint::Extern(outpoint)] {
+            for secondary in [TxoSealExt::Fallback(Outpoint::coinbase()), TxoSealExt::Noise(noise)] {
+                let seal = WLiquidSeal { primary, secondary };
+                assert_eq!(WLiquidSeal::parse(&seal.to_string()), Ok(seal));
+            }
+        }
+        assert!(matches!(WLiquidSeal::parse("~:3"), Err(TxoSealParseError::NoSeparator(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "bitcoin")]
+    fn auth_token_domain() {
+        use bp::seals::WTxoSeal;
+
+        let primary = WOutpoint::Wout(Vout::from_u32(0));
+        let secondary = TxoSealExt::Fallback(Outpoint::coinbase());
+        let liquid = WLiquidSeal { primary, secondary };
+        let bitcoin = WTxoSeal { primary, secondary };
+        assert_ne!(liquid.auth_token(), bitcoin.auth_token());
+    }
+}
@@ -0,0 +1,281 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 LNP/BP Laboratories,
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2025 RGB Consortium, Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Mock single-use seals for testing.
+//!
+//! The mock seal protocol doesn't rely on any external medium: a witness is just a list of the
+//! seals it closes together with the message they are closed over, identified by an arbitrary
+//! number. This allows constructing valid contract histories in tests without building
+//! transactions and commitment proofs.
+//!
+//! Mock seals provide no security and must never be used outside tests.
+
+use core::fmt::{self, Display, Formatter};
+use core::str::FromStr;
+
+use amplify::confinement::SmallOrdSet;
+use amplify::{ByteArray, Bytes32};
+use commit_verify::{CommitEncode, CommitId, CommitmentId, DigestExt, Sha256};
+use single_use_seals::{NoClientWitness, PublishedWitness, SealWitness, SingleUseSeal};
+use ultrasonic::{AuthToken, Opid};
+
+use crate::{AuthTokenVersion, RgbSeal, RgbSealDef, LIB_NAME_RGB};
+
+/// Mock single-use seal, identified by the witness which has defined it and a number.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Display)]
+#[display("{witness}:{no}")]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct MockSeal {
+    /// Id of the witness defining the seal.
+    pub witness: u64,
+    /// Number of the seal within the witness.
+    pub no: u32,
+}
+
+impl MockSeal {
+    /// Constructs a seal number `no` defined by the witness with id `witness`.
+    pub fn new(witness: u64, no: u32) -> Self { Self { witness, no } }
+}
+
+impl FromStr for MockSeal {
+    type Err = MockSealParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (witness, no) = s
+            .split_once(':')
+            .ok_or_else(|| MockSealParseError(s.to_owned()))?;
+        let witness = witness
+            .parse()
+            .map_err(|_| MockSealParseError(s.to_owned()))?;
+        let no = no.parse().map_err(|_| MockSealParseError(s.to_owned()))?;
+        Ok(Self { witness, no })
+    }
+}
+
+/// Mock single-use seal definition, which may be relative to the witness of an operation defining
+/// the seal.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::Wout(strict_dumb!()))]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = MockSealId)]
+pub enum MockSealDef {
+    /// Seal with the given number defined by the witness of the operation defining the seal.
+    #[strict_type(tag = 0)]
+    Wout(u32),
+
+    /// Fully defined seal.
+    #[strict_type(tag = 1)]
+    Extern(MockSeal),
+}
+
+impl Display for MockSealDef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MockSealDef::Wout(no) => write!(f, "~:{no}"),
+            MockSealDef::Extern(seal) => Display::fmt(seal, f),
+        }
+    }
+}
+
+impl FromStr for MockSealDef {
+    type Err = MockSealParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("~:") {
+            Some(no) => no
+                .parse()
+                .map(Self::Wout)
+                .map_err(|_| MockSealParseError(s.to_owned())),
+            None => s.parse().map(Self::Extern),
+        }
+    }
+}
+
+impl From<MockSeal> for MockSealDef {
+    fn from(seal: MockSeal) -> Self { Self::Extern(seal) }
+}
+
+/// Commitment to a [`MockSealDef`], used to produce seal [`AuthToken`]s.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct MockSealId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitmentId for MockSealId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:mock-seal#2025-06-04";
+}
+
+impl From<Sha256> for MockSealId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Mock seal closing witness, listing the closed seals and the message they are closed over.
+#[derive(Clone, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct MockWitness {
+    /// Witness id.
+    pub id: u64,
+    /// Seals closed by the witness.
+    pub closed: SmallOrdSet<MockSeal>,
+    /// Message over which the seals are closed.
+    pub message: Bytes32,
+}
+
+impl MockWitness {
+    /// Constructs a witness with id `id`, closing `seals` over the operation `opid`.
+    pub fn new(id: u64, seals: impl IntoIterator<Item = MockSeal>, opid: Opid) -> Self {
+        Self {
+            id,
+            closed: SmallOrdSet::from_iter_checked(seals),
+            message: Bytes32::from_byte_array(opid.to_byte_array()),
+        }
+    }
+
+    /// Constructs a complete seal closing witness out of this mock witness.
+    pub fn seal_witness(self) -> SealWitness<MockSeal> { SealWitness::new(self, NoClientWitness::new()) }
+}
+
+impl SingleUseSeal for MockSeal {
+    type Message = Bytes32;
+    type PubWitness = MockWitness;
+    type CliWitness = NoClientWitness<MockSeal>;
+
+    fn is_included(&self, _message: Self::Message, witness: &SealWitness<Self>) -> bool {
+        witness.published.closed.contains(self)
+    }
+}
+
+impl PublishedWitness<MockSeal> for MockWitness {
+    type PubId = u64;
+    type Error = MockSealError;
+
+    fn pub_id(&self) -> u64 { self.id }
+
+    fn verify_commitment(&self, proof: Bytes32) -> Result<(), Self::Error> {
+        if self.message != proof {
+            return Err(MockSealError { expected: self.message, found: proof });
+        }
+        Ok(())
+    }
+}
+
+/// mock witness commits to message {expected}, while seals are closed over {found}.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct MockSealError {
+    /// Message committed by the witness.
+    pub expected: Bytes32,
+    /// Message over which the seals were tried to be closed.
+    pub found: Bytes32,
+}
+
+/// invalid mock seal '{0}'.
+#[derive(Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub struct MockSealParseError(String);
+
+impl RgbSeal for MockSeal {
+    type Definition = MockSealDef;
+    type Published = MockWitness;
+    type Client = NoClientWitness<MockSeal>;
+    type WitnessId = u64;
+}
+
+impl RgbSealDef for MockSealDef {
+    type Src = MockSeal;
+    type ParseError = MockSealParseError;
+
+    fn auth_token(&self) -> AuthToken { AuthTokenVersion::V0.derive(self.commit_id().to_byte_array()) }
+
+    fn parse(s: &str) -> Result<Self, MockSealParseError> { s.parse() }
+
+    fn resolve(&self, witness_id: u64) -> MockSeal {
+        match *self {
+            MockSealDef::Wout(no) => MockSeal::new(witness_id, no),
+            MockSealDef::Extern(seal) => seal,
+        }
+    }
+
+    fn to_src(&self) -> Option<MockSeal> {
+        match *self {
+            MockSealDef::Wout(_) => None,
+            MockSealDef::Extern(seal) => Some(seal),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use single_use_seals::SealError;
+
+    use super::*;
+
+    #[test]
+    fn display() {
+        assert_eq!(MockSeal::new(1, 2).to_string(), "1:2");
+        assert_eq!(MockSealDef::Wout(2).to_string(), "~:2");
+        assert_eq!(MockSealDef::from(MockSeal::new(1, 2)).to_string(), "1:2");
+    }
+
+    #[test]
+    fn parse() {
+        for seal in [MockSealDef::Wout(2), MockSealDef::from(MockSeal::new(1, 2)), MockSealDef::Wout(u32::MAX)] {
+            assert_eq!(MockSealDef::parse(&seal.to_string()), Ok(seal));
+        }
+        assert!(MockSealDef::parse("~:").is_err());
+        assert!(MockSealDef::parse("1").is_err());
+        assert!(MockSealDef::parse("1:x").is_err());
+    }
+
+    #[test]
+    fn resolve() {
+        let seal = MockSeal::new(1, 2);
+        assert_eq!(MockSealDef::Wout(2).to_src(), None);
+        assert_eq!(MockSealDef::Wout(2).resolve(1), seal);
+        assert_eq!(MockSealDef::Extern(seal).to_src(), Some(seal));
+        assert_eq!(MockSealDef::Extern(seal).resolve(5), seal);
+        assert_ne!(MockSealDef::Wout(2).auth_token(), MockSealDef::Extern(seal).auth_token());
+    }
+
+    #[test]
+    fn seal_closing() {
+        let seal = MockSeal::new(1, 2);
+        let opid = Opid::from_byte_array([0xAD; 32]);
+        let msg = Bytes32::from_byte_array(opid.to_byte_array());
+        let witness = MockWitness::new(3, [seal], opid).seal_witness();
+        witness.verify_seal_closing(seal, msg).unwrap();
+        assert!(matches!(witness.verify_seal_closing(MockSeal::new(1, 3), msg), Err(SealError::NotIncluded(..))));
+        assert!(matches!(witness.verify_seal_closing(seal, Bytes32::zero()), Err(SealError::Published(_))));
+    }
+}
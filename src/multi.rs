@@ -0,0 +1,491 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 LNP/BP Laboratories,
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2025 RGB Consortium, Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Single-use seals of multi-layer contracts.
+//!
+//! A [`MultiSeal`] combines seals of two different single-use seal protocols (layers), allowing a
+//! contract to move its state between the layers: each operation closes seals on a single layer,
+//! using a witness from that layer, but may define seals on any of the layers. Contracts operating
+//! on more than two layers may nest the multi-layer seals, like
+//! `MultiSeal<TxoSeal, MultiSeal<LiquidSeal, PrimeSeal>>`.
+//!
+//! Seal definitions relative to a witness (like bitcoin witness transaction outputs) can be closed
+//! only if the witness belongs to the same layer; otherwise the definition is not closable (see
+//! [`RgbSealDef::is_closable`]); the verifier rejects such definitions, including the ones revealed
+//! after the operation witness was processed.
+
+use core::error::Error;
+use core::fmt::{self, Display, Formatter};
+
+use amplify::Bytes32;
+use commit_verify::{DigestExt, Sha256};
+use single_use_seals::{ClientSideWitness, PublishedWitness, SealWitness, SingleUseSeal};
+use strict_encoding::StrictDumb;
+use ultrasonic::AuthToken;
+
+use crate::{AuthTokenVersion, RgbSeal, RgbSealDef, WitnessCommitment, LIB_NAME_RGB};
+
+/// Tag used for deriving auth tokens of the multi-layer seal definitions from the layer and the
+/// auth token of the layer seal definition.
+pub const MULTI_SEAL_TAG: &str = "urn:lnp-bp:rgb:multi-seal#2025-06-12";
+
+/// Single-use seal belonging to one of two layers.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(inner)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::First(strict_dumb!()))]
+pub enum MultiSeal<A: RgbSeal, B: RgbSeal> {
+    /// Seal of the first layer.
+    #[strict_type(tag = 0)]
+    First(A),
+
+    /// Seal of the second layer.
+    #[strict_type(tag = 1)]
+    Second(B),
+}
+
+impl<A: RgbSeal, B: RgbSeal> MultiSeal<A, B> {
+    /// Converts a witness of the first layer into a multi-layer seal witness.
+    pub fn first_witness(witness: SealWitness<A>) -> SealWitness<Self> {
+        SealWitness::new(MultiWitness::First(witness.published), MultiAnchor::First(witness.client))
+    }
+
+    /// Converts a witness of the second layer into a multi-layer seal witness.
+    pub fn second_witness(witness: SealWitness<B>) -> SealWitness<Self> {
+        SealWitness::new(MultiWitness::Second(witness.published), MultiAnchor::Second(witness.client))
+    }
+}
+
+/// Definition of a single-use seal belonging to one of two layers.
+///
+/// The definition is displayed with a prefix naming its layer, like `first:<definition>`, which is
+/// required for parsing it back (see [`RgbSealDef::parse`]).
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::First(strict_dumb!()))]
+pub enum MultiSealDef<A: RgbSeal, B: RgbSeal> {
+    /// Seal definition for the first layer.
+    #[strict_type(tag = 0)]
+    #[display("first:{0}")]
+    First(A::Definition),
+
+    /// Seal definition for the second layer.
+    #[strict_type(tag = 1)]
+    #[display("second:{0}")]
+    Second(B::Definition),
+}
+
+/// Published part of a seal closing witness, belonging to one of two layers.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::First(strict_dumb!()))]
+pub enum MultiWitness<A: RgbSeal, B: RgbSeal> {
+    /// Published witness from the first layer.
+    #[strict_type(tag = 0)]
+    First(A::Published),
+
+    /// Published witness from the second layer.
+    #[strict_type(tag = 1)]
+    Second(B::Published),
+}
+
+/// Client-side part of a seal closing witness, belonging to one of two layers.
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::First(strict_dumb!()))]
+pub enum MultiAnchor<A: RgbSeal, B: RgbSeal> {
+    /// Client-side witness for the first layer.
+    #[strict_type(tag = 0)]
+    First(A::Client),
+
+    /// Client-side witness for the second layer.
+    #[strict_type(tag = 1)]
+    Second(B::Client),
+}
+
+/// Id of a seal closing witness, belonging to one of two layers.
+///
+/// The id is parameterized by the witness id types of the layers.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum MultiWitnessId<A, B> {
+    /// Id of a witness from the first layer.
+    First(A),
+
+    /// Id of a witness from the second layer.
+    Second(B),
+}
+
+impl<A: Display, B: Display> Display for MultiWitnessId<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiWitnessId::First(id) => Display::fmt(id, f),
+            MultiWitnessId::Second(id) => Display::fmt(id, f),
+        }
+    }
+}
+
+/// Proof produced by the client-side witness of one of two layers.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum MultiProof<A, B> {
+    /// Proof for the first layer.
+    First(A),
+
+    /// Proof for the second layer.
+    Second(B),
+}
+
+/// Errors of seal closing verification on one of two layers.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum MultiSealError<A, B> {
+    /// Error verifying the first layer.
+    First(A),
+
+    /// Error verifying the second layer.
+    Second(B),
+
+    /// Witness parts belong to different layers.
+    LayerMismatch,
+}
+
+impl<A: Display, B: Display> Display for MultiSealError<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiSealError::First(err) => Display::fmt(err, f),
+            MultiSealError::Second(err) => Display::fmt(err, f),
+            MultiSealError::LayerMismatch => f.write_str("seal closing witness parts belong to different layers."),
+        }
+    }
+}
+
+impl<A: Error, B: Error> Error for MultiSealError<A, B> {}
+
+/// Errors parsing a multi-layer seal definition.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum MultiParseError<A, B> {
+    /// The definition lacks a known layer prefix.
+    Layer(String),
+
+    /// Error parsing the definition for the first layer.
+    First(A),
+
+    /// Error parsing the definition for the second layer.
+    Second(B),
+}
+
+impl<A: Display, B: Display> Display for MultiParseError<A, B> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MultiParseError::Layer(s) => {
+                write!(f, "seal definition '{s}' lacks the layer prefix, which must be either 'first:' or 'second:'.")
+            }
+            MultiParseError::First(err) => write!(f, "invalid seal definition for the first layer. Details: {err}"),
+            MultiParseError::Second(err) => write!(f, "invalid seal definition for the second layer. Details: {err}"),
+        }
+    }
+}
+
+impl<A: Error, B: Error> Error for MultiParseError<A, B> {}
+
+/// Errors merging client-side witnesses of multi-layer seals.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum MultiMergeError {
+    /// client-side witnesses belong to different layers.
+    LayerMismatch,
+
+    /// client-side witnesses for the first layer do not match.
+    First,
+
+    /// client-side witnesses for the second layer do not match.
+    Second,
+}
+
+impl<A: RgbSeal, B: RgbSeal> SingleUseSeal for MultiSeal<A, B> {
+    type Message = Bytes32;
+    type PubWitness = MultiWitness<A, B>;
+    type CliWitness = MultiAnchor<A, B>;
+
+    fn is_included(&self, message: Self::Message, witness: &SealWitness<Self>) -> bool {
+        let message = message.to_byte_array();
+        match (self, &witness.published, &witness.client) {
+            (MultiSeal::First(seal), MultiWitness::First(published), MultiAnchor::First(client)) => {
+                seal.is_included(message.into(), &SealWitness::new(published.clone(), client.clone()))
+            }
+            (MultiSeal::Second(seal), MultiWitness::Second(published), MultiAnchor::Second(client)) => {
+                seal.is_included(message.into(), &SealWitness::new(published.clone(), client.clone()))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl<A: RgbSeal, B: RgbSeal> PublishedWitness<MultiSeal<A, B>> for MultiWitness<A, B> {
+    type PubId = MultiWitnessId<A::WitnessId, B::WitnessId>;
+    type Error =
+        MultiSealError<<A::Published as PublishedWitness<A>>::Error, <B::Published as PublishedWitness<B>>::Error>;
+
+    fn pub_id(&self) -> Self::PubId {
+        match self {
+            MultiWitness::First(published) => MultiWitnessId::First(published.pub_id()),
+            MultiWitness::Second(published) => MultiWitnessId::Second(published.pub_id()),
+        }
+    }
+
+    fn verify_commitment(
+        &self,
+        proof: MultiProof<<A::Client as ClientSideWitness>::Proof, <B::Client as ClientSideWitness>::Proof>,
+    ) -> Result<(), Self::Error> {
+        match (self, proof) {
+            (MultiWitness::First(published), MultiProof::First(proof)) => published
+                .verify_commitment(proof)
+                .map_err(MultiSealError::First),
+            (MultiWitness::Second(published), MultiProof::Second(proof)) => published
+                .verify_commitment(proof)
+                .map_err(MultiSealError::Second),
+            _ => Err(MultiSealError::LayerMismatch),
+        }
+    }
+}
+
+impl<A: RgbSeal, B: RgbSeal> ClientSideWitness for MultiAnchor<A, B> {
+    type Seal = MultiSeal<A, B>;
+    type Proof = MultiProof<<A::Client as ClientSideWitness>::Proof, <B::Client as ClientSideWitness>::Proof>;
+    type Error = MultiSealError<<A::Client as ClientSideWitness>::Error, <B::Client as ClientSideWitness>::Error>;
+
+    fn convolve_commit(&self, msg: Bytes32) -> Result<Self::Proof, Self::Error> {
+        let msg = msg.to_byte_array();
+        match self {
+            MultiAnchor::First(client) => client
+                .convolve_commit(msg.into())
+                .map(MultiProof::First)
+                .map_err(MultiSealError::First),
+            MultiAnchor::Second(client) => client
+                .convolve_commit(msg.into())
+                .map(MultiProof::Second)
+                .map_err(MultiSealError::Second),
+        }
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), impl Error>
+    where Self: Sized {
+        match (self, other) {
+            (MultiAnchor::First(client), MultiAnchor::First(other)) => {
+                client.merge(other).map_err(|_| MultiMergeError::First)
+            }
+            (MultiAnchor::Second(client), MultiAnchor::Second(other)) => {
+                client.merge(other).map_err(|_| MultiMergeError::Second)
+            }
+            _ => Err(MultiMergeError::LayerMismatch),
+        }
+    }
+}
+
+impl<A: RgbSeal, B: RgbSeal> RgbSeal for MultiSeal<A, B> {
+    type Definition = MultiSealDef<A, B>;
+    type Published = MultiWitness<A, B>;
+    type Client = MultiAnchor<A, B>;
+    type WitnessId = MultiWitnessId<A::WitnessId, B::WitnessId>;
+
+    fn fallback(&self) -> Option<Self> {
+        match self {
+            MultiSeal::First(seal) => seal.fallback().map(MultiSeal::First),
+            MultiSeal::Second(seal) => seal.fallback().map(MultiSeal::Second),
+        }
+    }
+
+    fn proves_fallback(witness: &SealWitness<Self>) -> bool {
+        match (&witness.published, &witness.client) {
+            (MultiWitness::First(published), MultiAnchor::First(client)) => {
+                A::proves_fallback(&SealWitness::new(published.clone(), client.clone()))
+            }
+            (MultiWitness::Second(published), MultiAnchor::Second(client)) => {
+                B::proves_fallback(&SealWitness::new(published.clone(), client.clone()))
+            }
+            _ => false,
+        }
+    }
+
+    fn commitment(witness: &SealWitness<Self>) -> Option<WitnessCommitment> {
+        match (&witness.published, &witness.client) {
+            (MultiWitness::First(published), MultiAnchor::First(client)) => {
+                A::commitment(&SealWitness::new(published.clone(), client.clone()))
+            }
+            (MultiWitness::Second(published), MultiAnchor::Second(client)) => {
+                B::commitment(&SealWitness::new(published.clone(), client.clone()))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<A: RgbSeal, B: RgbSeal> RgbSealDef for MultiSealDef<A, B> {
+    type Src = MultiSeal<A, B>;
+    type ParseError =
+        MultiParseError<<A::Definition as RgbSealDef>::ParseError, <B::Definition as RgbSealDef>::ParseError>;
+
+    // The layer is committed to, since the layers may use the same seal definitions, which must
+    // not produce the same auth token.
+    fn auth_token(&self) -> AuthToken {
+        let (layer, token) = match self {
+            MultiSealDef::First(seal) => (0u8, seal.auth_token()),
+            MultiSealDef::Second(seal) => (1u8, seal.auth_token()),
+        };
+        let id = Sha256::from_tag(MULTI_SEAL_TAG)
+            .with_raw(&[layer])
+            .with_raw(&token.to_fe256().to_u256().to_le_bytes())
+            .finish();
+        AuthTokenVersion::V0.derive(id)
+    }
+
+    fn parse(s: &str) -> Result<Self, Self::ParseError> {
+        match s.split_once(':') {
+            Some(("first", seal)) => A::Definition::parse(seal)
+                .map(MultiSealDef::First)
+                .map_err(MultiParseError::First),
+            Some(("second", seal)) => B::Definition::parse(seal)
+                .map(MultiSealDef::Second)
+                .map_err(MultiParseError::Second),
+            _ => Err(MultiParseError::Layer(s.to_owned())),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If the seal definition is relative to a witness, and the `witness_id` belongs to the other
+    /// layer. Use [`RgbSealDef::try_resolve`] for the definitions which may belong to a different
+    /// layer than the witness.
+    fn resolve(&self, witness_id: MultiWitnessId<A::WitnessId, B::WitnessId>) -> MultiSeal<A, B> {
+        self.try_resolve(witness_id)
+            .expect("seal definition is relative to a witness from a different layer")
+    }
+
+    // A seal defined relatively to a witness from another layer can't exist, thus it is never
+    // produced.
+    fn try_resolve(&self, witness_id: MultiWitnessId<A::WitnessId, B::WitnessId>) -> Option<MultiSeal<A, B>> {
+        match (self, witness_id) {
+            (MultiSealDef::First(seal), MultiWitnessId::First(id)) => Some(MultiSeal::First(seal.resolve(id))),
+            (MultiSealDef::Second(seal), MultiWitnessId::Second(id)) => Some(MultiSeal::Second(seal.resolve(id))),
+            (MultiSealDef::First(_), MultiWitnessId::Second(_))
+            | (MultiSealDef::Second(_), MultiWitnessId::First(_)) => self.to_src(),
+        }
+    }
+
+    fn to_src(&self) -> Option<MultiSeal<A, B>> {
+        match self {
+            MultiSealDef::First(seal) => seal.to_src().map(MultiSeal::First),
+            MultiSealDef::Second(seal) => seal.to_src().map(MultiSeal::Second),
+        }
+    }
+
+    fn is_closable(&self, witness: &MultiWitness<A, B>) -> bool {
+        match (self, witness) {
+            (MultiSealDef::First(seal), MultiWitness::First(published)) => seal.is_closable(published),
+            (MultiSealDef::Second(seal), MultiWitness::Second(published)) => seal.is_closable(published),
+            (MultiSealDef::First(seal), MultiWitness::Second(_)) => seal.to_src().is_some(),
+            (MultiSealDef::Second(seal), MultiWitness::First(_)) => seal.to_src().is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use amplify::ByteArray;
+    use single_use_seals::SealError;
+    use ultrasonic::Opid;
+
+    use super::*;
+    use crate::mock::{MockSeal, MockSealDef, MockWitness};
+
+    type Seal = MultiSeal<MockSeal, MockSeal>;
+    type SealDef = MultiSealDef<MockSeal, MockSeal>;
+
+    #[test]
+    fn resolve() {
+        let witness = MockWitness::new(5, [], Opid::from_byte_array([0; 32]));
+        let first = Seal::first_witness(witness.clone().seal_witness()).published;
+        let second = Seal::second_witness(witness.seal_witness()).published;
+
+        let wout = SealDef::First(MockSealDef::Wout(1));
+        assert_eq!(wout.to_src(), None);
+        assert_eq!(wout.resolve(MultiWitnessId::First(5)), Seal::First(MockSeal::new(5, 1)));
+        assert!(wout.is_closable(&first));
+        assert!(!wout.is_closable(&second));
+        assert_eq!(wout.try_resolve(MultiWitnessId::First(5)), Some(Seal::First(MockSeal::new(5, 1))));
+        assert_eq!(wout.try_resolve(MultiWitnessId::Second(5)), None);
+
+        let ext = SealDef::Second(MockSealDef::Extern(MockSeal::new(3, 1)));
+        assert_eq!(ext.to_src(), Some(Seal::Second(MockSeal::new(3, 1))));
+        assert_eq!(ext.resolve(MultiWitnessId::First(5)), Seal::Second(MockSeal::new(3, 1)));
+        assert_eq!(ext.try_resolve(MultiWitnessId::First(5)), Some(Seal::Second(MockSeal::new(3, 1))));
+        assert!(ext.is_closable(&first));
+        assert!(ext.is_closable(&second));
+    }
+
+    #[test]
+    fn auth_token() {
+        let first = SealDef::First(MockSealDef::Wout(1));
+        let second = SealDef::Second(MockSealDef::Wout(1));
+        assert_ne!(first.auth_token(), second.auth_token());
+        assert_ne!(first.auth_token(), MockSealDef::Wout(1).auth_token());
+        assert_eq!(first.auth_token(), SealDef::First(MockSealDef::Wout(1)).auth_token());
+    }
+
+    #[test]
+    fn parse() {
+        let ext = SealDef::First(MockSealDef::Extern(MockSeal::new(3, 1)));
+        assert_eq!(ext.to_string(), "first:3:1");
+        assert_eq!(SealDef::parse(&ext.to_string()), Ok(ext));
+        for seal in [SealDef::First(MockSealDef::Wout(1)), SealDef::Second(MockSealDef::Wout(1))] {
+            assert_eq!(SealDef::parse(&seal.to_string()), Ok(seal));
+        }
+
+        assert_eq!(SealDef::parse("3:1"), Err(MultiParseError::Layer(s!("3:1"))));
+        assert_eq!(SealDef::parse("third:3:1"), Err(MultiParseError::Layer(s!("third:3:1"))));
+        assert!(matches!(SealDef::parse("first:x"), Err(MultiParseError::First(_))));
+        assert!(matches!(SealDef::parse("second:x"), Err(MultiParseError::Second(_))));
+    }
+
+    #[test]
+    fn seal_closing() {
+        let opid = Opid::from_byte_array([0xAD; 32]);
+        let msg = Bytes32::from_byte_array(opid.to_byte_array());
+        let closed = MockSeal::new(1, 0);
+        let witness = Seal::second_witness(MockWitness::new(2, [closed], opid).seal_witness());
+        assert_eq!(witness.published.pub_id(), MultiWitnessId::Second(2));
+        witness
+            .verify_seal_closing(Seal::Second(closed), msg)
+            .unwrap();
+        assert!(matches!(witness.verify_seal_closing(Seal::First(closed), msg), Err(SealError::NotIncluded(..))));
+
+        let mut witness = witness;
+        witness.client = MultiAnchor::First(default!());
+        assert_eq!(witness.published.verify_commitment(MultiProof::First(msg)), Err(MultiSealError::LayerMismatch));
+        assert!(witness
+            .client
+            .merge(MultiAnchor::Second(default!()))
+            .is_err());
+    }
+}
@@ -0,0 +1,537 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 LNP/BP Laboratories,
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2025 RGB Consortium, Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Single-use seals for RGB Prime.
+//!
+//! RGB Prime seals are not bound to bitcoin transaction outputs; instead, they are closed by a
+//! publication in a header chain maintained by a sequencer. Each block header commits to a Merkle
+//! tree of closing records; each record lists the seals it closes and commits to the messages over
+//! which they are closed with a multiprotocol commitment.
+//!
+//! The validity of the header chain itself, including the guarantee that a seal is closed in no
+//! more than one record, is not a part of the seal closing verification, the same way as bitcoin
+//! transaction mining is not verified by the bitcoin seals.
+
+use core::error::Error;
+
+use amplify::confinement::{SmallOrdMap, SmallOrdSet, TinyVec};
+use amplify::{hex, ByteArray, Bytes32};
+use commit_verify::mpc::{self, InvalidProof};
+use commit_verify::{CommitEncode, CommitId, CommitmentId, DigestExt, Sha256};
+use single_use_seals::{ClientSideWitness, PublishedWitness, SealWitness, SingleUseSeal};
+use ultrasonic::AuthToken;
+
+use crate::{AuthTokenVersion, RgbSeal, RgbSealDef, LIB_NAME_RGB};
+
+/// Single-use seal of RGB Prime.
+///
+/// Seals are random identifiers, which do not reveal their owner; the ownership is proven by the
+/// ability to put a closing record into the sequencer header chain.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = PrimeSealId)]
+pub struct PrimeSeal(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl PrimeSeal {
+    /// Creates a new seal using entropy from a pre-initialized SHA256 engine and a deterministic
+    /// incremental `nonce`, preventing from creating the same seal twice.
+    pub fn with(mut noise_engine: Sha256, nonce: u64) -> Self {
+        noise_engine.input_raw(&nonce.to_be_bytes());
+        Self::from_byte_array(noise_engine.finish())
+    }
+}
+
+/// Commitment to a [`PrimeSeal`], used to produce seal [`AuthToken`]s.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct PrimeSealId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitmentId for PrimeSealId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:prime-seal#2025-06-04";
+}
+
+impl From<Sha256> for PrimeSealId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Seals closed by a closing record together with the messages they are closed over.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = PrimeBundleId)]
+pub struct PrimeBundle {
+    /// Map from a seal closed by the record to the message it is closed over.
+    pub map: SmallOrdMap<PrimeSeal, mpc::Message>,
+}
+
+/// Commitment to a [`PrimeBundle`], which is put into the multiprotocol commitment of a closing
+/// record.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct PrimeBundleId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitmentId for PrimeBundleId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:prime-bundle#2025-06-04";
+}
+
+impl From<Sha256> for PrimeBundleId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Closing record published in a block of the header chain.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = PrimeRecordId)]
+pub struct PrimeRecord {
+    /// Seals closed by the record.
+    pub seals: SmallOrdSet<PrimeSeal>,
+    /// Multiprotocol commitment to the messages over which the seals are closed.
+    pub commitment: mpc::Commitment,
+}
+
+/// Identifier of a [`PrimeRecord`], used as a leaf of the block records Merkle tree.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct PrimeRecordId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitmentId for PrimeRecordId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:prime-record#2025-06-04";
+}
+
+impl From<Sha256> for PrimeRecordId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Tag used for the nodes of the block records Merkle tree.
+pub const PRIME_NODE_TAG: &str = "urn:lnp-bp:rgb:prime-node#2025-06-04";
+
+/// Merkle root of the closing records in a block.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Default)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct PrimeRecordRoot(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl PrimeRecordRoot {
+    /// Computes the Merkle root of the records with the provided ids.
+    ///
+    /// The tree is padded with zero leaves up to the nearest power of two.
+    pub fn with(records: &[PrimeRecordId]) -> Self {
+        let width = records.len().next_power_of_two();
+        let mut level = records
+            .iter()
+            .map(|id| id.to_byte_array())
+            .chain(core::iter::repeat([0u8; 32]))
+            .take(width)
+            .collect::<Vec<_>>();
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| merkle_node(pair[0], pair[1]))
+                .collect();
+        }
+        Self::from_byte_array(level[0])
+    }
+}
+
+fn merkle_node(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+    let mut engine = Sha256::from_tag(PRIME_NODE_TAG);
+    engine.input_raw(&left);
+    engine.input_raw(&right);
+    engine.finish()
+}
+
+/// Proof of inclusion of a closing record into a block records Merkle tree.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Default)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct PrimeRecordProof {
+    /// Position of the record in the tree.
+    pub pos: u32,
+    /// Sibling nodes on the path from the record to the root.
+    pub path: TinyVec<Bytes32>,
+}
+
+impl PrimeRecordProof {
+    /// Constructs the proof for the record at position `pos` among `records`.
+    ///
+    /// # Panics
+    ///
+    /// If the `pos` is outside the `records`.
+    pub fn with(records: &[PrimeRecordId], pos: u32) -> Self {
+        assert!((pos as usize) < records.len(), "record position is out of range");
+        let width = records.len().next_power_of_two();
+        let mut level = records
+            .iter()
+            .map(|id| id.to_byte_array())
+            .chain(core::iter::repeat([0u8; 32]))
+            .take(width)
+            .collect::<Vec<_>>();
+        let mut path = vec![];
+        let mut index = pos as usize;
+        while level.len() > 1 {
+            path.push(Bytes32::from_byte_array(level[index ^ 1]));
+            level = level
+                .chunks(2)
+                .map(|pair| merkle_node(pair[0], pair[1]))
+                .collect();
+            index /= 2;
+        }
+        Self { pos, path: TinyVec::from_checked(path) }
+    }
+
+    /// Computes the Merkle root from the record id and the proof.
+    pub fn convolve(&self, record: PrimeRecordId) -> PrimeRecordRoot {
+        let mut node = record.to_byte_array();
+        for (depth, sibling) in self.path.iter().enumerate() {
+            node = if (self.pos >> depth) & 1 == 0 {
+                merkle_node(node, sibling.to_byte_array())
+            } else {
+                merkle_node(sibling.to_byte_array(), node)
+            };
+        }
+        PrimeRecordRoot::from_byte_array(node)
+    }
+}
+
+/// Header of a block in the sequencer header chain, serving as a published witness for
+/// [`PrimeSeal`].
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[derive(CommitEncode)]
+#[commit_encode(strategy = strict, id = PrimeBlockId)]
+pub struct PrimeHeader {
+    /// Version of the header.
+    pub version: u8,
+    /// Id of the previous block in the header chain.
+    pub prev_block: PrimeBlockId,
+    /// Height of the block in the header chain.
+    pub height: u64,
+    /// Block timestamp, in seconds since the UNIX epoch.
+    pub timestamp: u64,
+    /// Merkle root of the closing records in the block.
+    pub records: PrimeRecordRoot,
+}
+
+impl PrimeHeader {
+    /// Computes the id of the block.
+    pub fn block_id(&self) -> PrimeBlockId { self.commit_id() }
+}
+
+/// Identifier of a block in the sequencer header chain.
+#[derive(Wrapper, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, From, Default)]
+#[wrapper(Deref, BorrowSlice, Display, FromStr, Hex, Index, RangeOps)]
+#[derive(StrictType, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct PrimeBlockId(
+    #[from]
+    #[from([u8; 32])]
+    Bytes32,
+);
+
+impl CommitmentId for PrimeBlockId {
+    const TAG: &'static str = "urn:lnp-bp:rgb:prime-block#2025-06-04";
+}
+
+impl From<Sha256> for PrimeBlockId {
+    fn from(hasher: Sha256) -> Self { hasher.finish().into() }
+}
+
+/// Client-side witness for the RGB Prime seals.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+pub struct PrimeAnchor {
+    /// Seals closed by the record and the messages over which they are closed.
+    pub bundle: PrimeBundle,
+    /// The protocol under which the client-side witness is valid.
+    pub mpc_protocol: mpc::ProtocolId,
+    /// The inclusion proof of the bundle under the [`Self::mpc_protocol`] into the record
+    /// commitment.
+    pub mpc_proof: mpc::MerkleProof,
+    /// The inclusion proof of the record into the block.
+    pub record_proof: PrimeRecordProof,
+}
+
+impl SingleUseSeal for PrimeSeal {
+    type Message = mpc::Message;
+    type PubWitness = PrimeHeader;
+    type CliWitness = PrimeAnchor;
+
+    fn is_included(&self, message: Self::Message, witness: &SealWitness<Self>) -> bool {
+        witness.client.bundle.map.get(self) == Some(&message)
+    }
+}
+
+impl PublishedWitness<PrimeSeal> for PrimeHeader {
+    type PubId = PrimeBlockId;
+    type Error = PrimeSealError;
+
+    fn pub_id(&self) -> PrimeBlockId { self.block_id() }
+
+    fn verify_commitment(&self, proof: PrimeRecordRoot) -> Result<(), Self::Error> {
+        if self.records != proof {
+            return Err(PrimeSealError::RecordNotIncluded);
+        }
+        Ok(())
+    }
+}
+
+impl ClientSideWitness for PrimeAnchor {
+    type Proof = PrimeRecordRoot;
+    type Seal = PrimeSeal;
+    type Error = PrimeAnchorError;
+
+    fn convolve_commit(&self, msg: mpc::Message) -> Result<PrimeRecordRoot, Self::Error> {
+        if self.bundle.map.values().all(|m| *m != msg) {
+            return Err(PrimeAnchorError::Message(msg));
+        }
+        let bundle_id = self.bundle.commit_id();
+        let mpc_message = mpc::Message::from_byte_array(bundle_id.to_byte_array());
+        let commitment = self.mpc_proof.convolve(self.mpc_protocol, mpc_message)?;
+        let seals = SmallOrdSet::from_iter_checked(self.bundle.map.keys().copied());
+        let record = PrimeRecord { seals, commitment };
+        Ok(self.record_proof.convolve(record.commit_id()))
+    }
+
+    fn merge(&mut self, other: Self) -> Result<(), impl Error>
+    where Self: Sized {
+        if *self != other {
+            return Err(PrimeAnchorError::Mismatch);
+        }
+        Ok(())
+    }
+}
+
+/// Errors verifying RGB Prime seal closing with a provided block header.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum PrimeSealError {
+    /// the closing record is not included into the block.
+    RecordNotIncluded,
+}
+
+/// Errors in the client-side witness of RGB Prime seals.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Display, Error, From)]
+#[display(doc_comments)]
+pub enum PrimeAnchorError {
+    /// invalid multiprotocol commitment proof.
+    #[from]
+    Mpc(InvalidProof),
+
+    /// message {0} is not a part of the anchor.
+    Message(mpc::Message),
+
+    /// anchors for the same block do not match.
+    Mismatch,
+}
+
+impl RgbSeal for PrimeSeal {
+    type Definition = PrimeSeal;
+    type Published = PrimeHeader;
+    type Client = PrimeAnchor;
+    type WitnessId = PrimeBlockId;
+}
+
+/// RGB Prime seals are never defined relatively to a witness, thus a seal serves as its own
+/// definition.
+impl RgbSealDef for PrimeSeal {
+    type Src = PrimeSeal;
+    type ParseError = hex::Error;
+
+    fn auth_token(&self) -> AuthToken { AuthTokenVersion::V0.derive(self.commit_id().to_byte_array()) }
+
+    fn parse(s: &str) -> Result<Self, hex::Error> { s.parse() }
+
+    fn resolve(&self, _witness_id: PrimeBlockId) -> PrimeSeal { *self }
+
+    fn to_src(&self) -> Option<PrimeSeal> { Some(*self) }
+}
+
+#[cfg(test)]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use amplify::confinement::Confined;
+    use amplify::num::u5;
+    use commit_verify::mpc::{MerkleTree, Method, MultiSource};
+    use commit_verify::TryCommitVerify;
+    use single_use_seals::SealError;
+    use strict_encoding::StrictDumb;
+
+    use super::*;
+
+    fn seal(no: u8) -> PrimeSeal { PrimeSeal::from_byte_array([no; 32]) }
+
+    fn setup() -> (mpc::Message, SealWitness<PrimeSeal>) {
+        let msg = mpc::Message::from_byte_array([0x4D; 32]);
+        let other = mpc::Message::from_byte_array([0x4E; 32]);
+        let bundle = PrimeBundle {
+            map: SmallOrdMap::from_checked(bmap! { seal(1) => msg, seal(2) => other }),
+        };
+
+        let protocol = mpc::ProtocolId::from_byte_array([0xAD; 32]);
+        let source = MultiSource {
+            method: Method::Sha256t,
+            min_depth: u5::with(3),
+            messages: Confined::from_checked(bmap! {
+                protocol => mpc::Message::from_byte_array(bundle.commit_id().to_byte_array())
+            }),
+            static_entropy: Some(0xFE),
+        };
+        let merkle_tree = MerkleTree::try_commit(&source).unwrap();
+        let (_, mpc_proof) = merkle_tree.clone().into_proofs().next().unwrap();
+
+        let record = PrimeRecord {
+            seals: SmallOrdSet::from_iter_checked(bundle.map.keys().copied()),
+            commitment: merkle_tree.commit_id(),
+        };
+        let records = [
+            PrimeRecordId::from_byte_array([0xA0; 32]),
+            record.commit_id(),
+            PrimeRecordId::from_byte_array([0xA2; 32]),
+        ];
+        let header = PrimeHeader {
+            version: 0,
+            prev_block: strict_dumb!(),
+            height: 1,
+            timestamp: 1_700_000_000,
+            records: PrimeRecordRoot::with(&records),
+        };
+        let anchor = PrimeAnchor {
+            bundle,
+            mpc_protocol: protocol,
+            mpc_proof,
+            record_proof: PrimeRecordProof::with(&records, 1),
+        };
+        (msg, SealWitness::new(header, anchor))
+    }
+
+    #[test]
+    fn auth_token() {
+        assert_eq!(seal(0).auth_token(), seal(0).auth_token());
+        assert_ne!(seal(0).auth_token(), seal(1).auth_token());
+    }
+
+    #[test]
+    fn resolve() {
+        let block_id = PrimeBlockId::from_byte_array([0xAD; 32]);
+        assert_eq!(seal(0).resolve(block_id), seal(0));
+    }
+
+    #[test]
+    fn parse() {
+        for nonce in 0..16 {
+            assert_eq!(PrimeSeal::parse(&seal(nonce).to_string()), Ok(seal(nonce)));
+        }
+        assert!(PrimeSeal::parse("seal").is_err());
+    }
+
+    #[test]
+    fn to_src() {
+        assert_eq!(seal(0).to_src(), Some(seal(0)));
+    }
+
+    #[test]
+    fn is_closable() {
+        assert!(seal(0).is_closable(&PrimeHeader::strict_dumb()));
+    }
+
+    #[test]
+    fn record_proofs() {
+        let records = (0u8..5)
+            .map(|no| PrimeRecordId::from_byte_array([no; 32]))
+            .collect::<Vec<_>>();
+        let root = PrimeRecordRoot::with(&records);
+        for pos in 0..5 {
+            let proof = PrimeRecordProof::with(&records, pos);
+            assert_eq!(proof.path.len(), 3);
+            assert_eq!(proof.convolve(records[pos as usize]), root);
+            assert_ne!(proof.convolve(records[(pos as usize + 1) % 5]), root);
+        }
+        assert_eq!(PrimeRecordRoot::with(&records[..1]).to_byte_array(), records[0].to_byte_array());
+    }
+
+    #[test]
+    fn seal_closing() {
+        let (msg, witness) = setup();
+        assert!(seal(1).is_included(msg, &witness));
+        witness.verify_seal_closing(seal(1), msg).unwrap();
+        witness
+            .verify_seals_closing([seal(1), seal(2)], msg)
+            .unwrap_err();
+
+        assert!(!seal(3).is_included(msg, &witness));
+        assert!(matches!(witness.verify_seal_closing(seal(3), msg), Err(SealError::NotIncluded(..))));
+
+        let unknown = mpc::Message::from_byte_array([0x4F; 32]);
+        let mut anchor = witness.client.clone();
+        anchor.bundle.map.insert(seal(3), unknown).unwrap();
+        let witness = SealWitness::<PrimeSeal>::new(witness.published, anchor);
+        assert!(matches!(witness.verify_seal_closing(seal(3), unknown), Err(SealError::Published(_))));
+    }
+
+    #[test]
+    fn seal_wrong_block() {
+        let (msg, mut witness) = setup();
+        witness.client.record_proof.pos = 0;
+        assert!(matches!(
+            witness.verify_seal_closing(seal(1), msg),
+            Err(SealError::Published(PrimeSealError::RecordNotIncluded))
+        ));
+    }
+}
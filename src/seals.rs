@@ -22,12 +22,14 @@
 // or implied. See the License for the specific language governing permissions and limitations under
 // the License.
 
+use core::error::Error;
 use core::fmt::{Debug, Display};
 use core::hash::Hash;
 
-use single_use_seals::{ClientSideWitness, PublishedWitness, SingleUseSeal};
+use single_use_seals::{ClientSideWitness, PublishedWitness, SealWitness, SingleUseSeal};
 use strict_encoding::{StrictDecode, StrictDumb, StrictEncode};
-use ultrasonic::AuthToken;
+use ultrasonic::aluvm::FIELD_ORDER_SECP;
+use ultrasonic::{fe256, AuthToken};
 
 /// A type which serves as a definition of a single-use seal for RGB contracts.
 ///
@@ -41,9 +43,22 @@ pub trait RgbSealDef: Clone + Eq + Debug + Display + StrictDumb + StrictEncode +
     /// definition is applicable.
     type Src: RgbSeal;
 
+    /// Error type returned by [`Self::parse`].
+    type ParseError: Error;
+
     /// Convert seal definition into an [`AuthToken`] for the SONIC computer.
     fn auth_token(&self) -> AuthToken;
 
+    /// Parses a seal definition from its string representation, as produced by the [`Display`]
+    /// implementation.
+    ///
+    /// # Nota bene
+    ///
+    /// This is not a [`core::str::FromStr`] requirement, since some seal definitions (like
+    /// `WTxoSeal`) are defined in other crates and can't implement it here. Seal definitions
+    /// implementing [`core::str::FromStr`] should just call into it.
+    fn parse(s: &str) -> Result<Self, Self::ParseError>;
+
     /// Resolve seal definition into a complete single-use seal instance using the provided witness
     /// id information.
     ///
@@ -56,11 +71,31 @@ pub trait RgbSealDef: Clone + Eq + Debug + Display + StrictDumb + StrictEncode +
         witness_id: <<Self::Src as SingleUseSeal>::PubWitness as PublishedWitness<Self::Src>>::PubId,
     ) -> Self::Src;
 
+    /// Resolve seal definition into a complete single-use seal instance like [`Self::resolve`],
+    /// unless the seal can't be defined relatively to the witness with the provided id (for
+    /// instance, since the witness belongs to a different layer of a multi-layer seal).
+    ///
+    /// The default implementation always succeeds, calling into [`Self::resolve`].
+    fn try_resolve(
+        &self,
+        witness_id: <<Self::Src as SingleUseSeal>::PubWitness as PublishedWitness<Self::Src>>::PubId,
+    ) -> Option<Self::Src> {
+        Some(self.resolve(witness_id))
+    }
+
     /// Try to convert this seal definition into a complete single-use seal instance.
     ///
     /// The operation may result in `None` if additional information about the seal witness is
     /// required. In this case use [`Self::resolve`] method.
     fn to_src(&self) -> Option<Self::Src>;
+
+    /// Checks whether a seal, resolved from this definition using the provided `witness` (see
+    /// [`Self::resolve`]), can be closed in the future.
+    ///
+    /// For instance, for bitcoin UTXO-based seals this requires the witness transaction to have a
+    /// spendable output with the number used by the seal definition. Seal definitions which are
+    /// not relative to a witness must always return `true`, which is the default implementation.
+    fn is_closable(&self, _witness: &<Self::Src as SingleUseSeal>::PubWitness) -> bool { true }
 }
 
 /// A type which serves as a single-use seal protocol implementation for RGB contracts.
@@ -70,21 +105,171 @@ pub trait RgbSeal:
     /// A type providing corresponding single-use seal definitions.
     type Definition: RgbSealDef<Src = Self>;
     /// A type for the published part of the single-use seal witness.
-    type Published: PublishedWitness<Self, PubId = Self::WitnessId>;
+    type Published: PublishedWitness<Self, PubId = Self::WitnessId> + Clone + StrictDumb + StrictEncode + StrictDecode;
     /// A type for the client-side part of the single-use seal witness.
-    type Client: ClientSideWitness;
+    type Client: ClientSideWitness<Seal = Self> + Clone + StrictDumb + StrictEncode + StrictDecode;
     /// A type for the id information about the single-use seal witness.
     type WitnessId: Copy + Ord + Hash + Debug + Display;
+
+    /// Returns a fallback seal, which may be closed instead of this seal once its primary
+    /// definition can't be closed with a valid commitment anymore.
+    ///
+    /// The verification procedure tries the fallback seal only for the witnesses which do not
+    /// close the seal itself; a witness closing a seal through its fallback must prove that the
+    /// primary definition can't be closed anymore (see [`Self::proves_fallback`]) and contain a
+    /// valid commitment for the fallback seal.
+    ///
+    /// Protocols with no fallback seals return `None`, which is the default implementation.
+    fn fallback(&self) -> Option<Self> { None }
+
+    /// Checks whether the `witness` proves that the primary definitions of the seals it closes
+    /// through their fallback seals (see [`Self::fallback`]) can't be closed anymore.
+    ///
+    /// Without such a proof, a fallback seal may be closed while its primary seal remains unspent,
+    /// allowing the same state to be spent twice. The default implementation returns `false`,
+    /// rejecting all the fallback closings.
+    ///
+    /// # Nota bene
+    ///
+    /// None of the seals provided by this library can prove a fallback closing yet: bitcoin anchors
+    /// do not support fallback proofs until bp-core implements them. Thus, the verification
+    /// currently rejects any closing through a fallback seal with
+    /// [`crate::VerificationError::FallbackUnproven`].
+    fn proves_fallback(_witness: &SealWitness<Self>) -> bool { false }
+
+    /// Returns information about the commitment made by a seal closing `witness` in one of its
+    /// outputs.
+    ///
+    /// The method is called only for the witnesses which passed the verification. Protocols not
+    /// committing through witness outputs return `None`, which is the default implementation.
+    fn commitment(_witness: &SealWitness<Self>) -> Option<WitnessCommitment> { None }
 }
 
-// Below are capabilities constants used in the standard library:
+/// Method used by a witness to commit to the messages over which the seals are closed.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum CommitmentMethod {
+    /// Commitment tweaking the key of a taproot output (tapret).
+    Tapret,
+
+    /// Commitment placed into an `OP_RETURN` output (opret).
+    Opret,
+}
+
+/// Information about the commitment made by a seal closing witness.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Display)]
+#[display("{method}:{output}")]
+pub struct WitnessCommitment {
+    /// Commitment method.
+    pub method: CommitmentMethod,
+    /// Index of the witness output containing the commitment.
+    pub output: u32,
+}
+
+/// Version of the procedure deriving an [`AuthToken`] from a 32-byte commitment id of a seal
+/// definition.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug, Default, Display)]
+#[display(lowercase)]
+// The hidden variant is usable, it is just not a part of the public API yet.
+#[allow(clippy::manual_non_exhaustive)]
+pub enum AuthTokenVersion {
+    /// The commitment id is truncated to its first 30 bytes, which always fit into a field element
+    /// with no overflows, providing a 120-bit collision resistance.
+    ///
+    /// This is the version used by all seal definitions provided by this library.
+    #[default]
+    V0,
+
+    /// The full-width commitment id, taken as a little-endian 256-bit number, is reduced modulo the
+    /// order of the secp256k1 field.
+    ///
+    /// # Nota bene
+    ///
+    /// Tokens of this version may exceed 30 bytes and thus can't be serialized or displayed with
+    /// the token representation of the current SONIC version (doing so panics in debug builds).
+    /// The version is reserved for the future seal definitions and is hidden from the public API
+    /// until the token representation supports it.
+    #[doc(hidden)]
+    V1,
+}
+
+impl AuthTokenVersion {
+    /// Derives an [`AuthToken`] from the commitment id of a seal definition.
+    pub fn derive(self, id: [u8; 32]) -> AuthToken {
+        match self {
+            AuthTokenVersion::V0 => {
+                let mut shortened_id = [0u8; 30];
+                shortened_id.copy_from_slice(&id[0..30]);
+                AuthToken::from_byte_array(shortened_id)
+            }
+            AuthTokenVersion::V1 => {
+                let val = fe256::from(id).to_u256() % FIELD_ORDER_SECP;
+                AuthToken::from(fe256::from(val))
+            }
+        }
+    }
+}
 
 #[cfg(any(feature = "bitcoin", feature = "liquid"))]
+pub mod txo {
+    use core::num::ParseIntError;
+    use core::str::FromStr;
+
+    use amplify::hex::{self, FromHex};
+    use amplify::Bytes;
+    use bp::seals::{Noise, TxoSealExt, WOutpoint};
+    use bp::{Outpoint, OutpointParseError, Vout};
+
+    /// Errors parsing TxO-based seal definitions.
+    #[derive(Clone, PartialEq, Eq, Debug, Display, Error, From)]
+    #[display(doc_comments)]
+    pub enum TxoSealParseError {
+        /// seal definition '{0}' lacks the separator '/' between the primary and the secondary
+        /// seals.
+        NoSeparator(String),
+
+        /// invalid witness output number in a seal definition. Details: {0}
+        #[from]
+        Wout(ParseIntError),
+
+        /// invalid outpoint in a seal definition. Details: {0}
+        #[from]
+        Outpoint(OutpointParseError),
+
+        /// invalid noise in a seal definition. Details: {0}
+        #[from]
+        Noise(hex::Error),
+    }
+
+    /// Parses a TxO-based seal definition in the `{primary}/{secondary}` form, where the primary
+    /// seal is either an outpoint or a witness output `~:{vout}`, and the secondary seal is either
+    /// a fallback outpoint or a hex-encoded noise.
+    pub(crate) fn parse_txo_def(s: &str) -> Result<(WOutpoint, TxoSealExt), TxoSealParseError> {
+        let (primary, secondary) = s
+            .split_once('/')
+            .ok_or_else(|| TxoSealParseError::NoSeparator(s.to_owned()))?;
+        let primary = match primary.strip_prefix("~:") {
+            Some(wout) => WOutpoint::Wout(Vout::from_str(wout)?),
+            None => WOutpoint::Extern(Outpoint::from_str(primary)?),
+        };
+        let secondary = if secondary.contains(':') {
+            TxoSealExt::Fallback(Outpoint::from_str(secondary)?)
+        } else {
+            TxoSealExt::Noise(Noise::from(Bytes::<40>::from_hex(secondary)?))
+        };
+        Ok((primary, secondary))
+    }
+}
+
+// Below are capabilities constants used in the standard library:
+
+#[cfg(feature = "bitcoin")]
 pub mod bitcoin {
-    use bp::seals::{Anchor, TxoSeal, WOutpoint, WTxoSeal};
+    use bp::seals::{Anchor, Noise, TxoSeal, TxoSealExt, WOutpoint, WTxoSeal};
     use bp::{Outpoint, Tx, Txid};
-    use commit_verify::CommitId;
+    use commit_verify::{CommitId, DigestExt, Sha256};
 
+    use super::txo::{parse_txo_def, TxoSealParseError};
     use super::*;
 
     impl RgbSeal for TxoSeal {
@@ -92,19 +277,49 @@ pub mod bitcoin {
         type Published = Tx;
         type Client = Anchor;
         type WitnessId = Txid;
+
+        /// The fallback seal is the one defined by [`TxoSealExt::Fallback`], unless it points to
+        /// the same outpoint as the primary seal.
+        fn fallback(&self) -> Option<Self> {
+            match self.secondary {
+                TxoSealExt::Fallback(outpoint) if outpoint != self.primary => {
+                    Some(TxoSeal { primary: outpoint, secondary: self.secondary })
+                }
+                TxoSealExt::Fallback(_) | TxoSealExt::Noise(_) => None,
+            }
+        }
+
+        /// Fallback proofs are not yet supported by the bp-core anchors, which thus never prove a
+        /// fallback closing; closings through [`TxoSealExt::Fallback`] are rejected until they are.
+        fn proves_fallback(witness: &SealWitness<Self>) -> bool {
+            witness.client.is_fallback() && witness.client.verify_fallback().is_ok()
+        }
+
+        /// The commitment method is defined by the presence of a tapret proof in the anchor. The
+        /// commitment is located in the first taproot output of the witness transaction for the
+        /// tapret method, and in its first `OP_RETURN` output for the opret method.
+        fn commitment(witness: &SealWitness<Self>) -> Option<WitnessCommitment> {
+            let method = match witness.client.dbc_proof {
+                Some(_) => CommitmentMethod::Tapret,
+                None => CommitmentMethod::Opret,
+            };
+            let output = witness.published.outputs().position(|out| match method {
+                CommitmentMethod::Tapret => out.script_pubkey.is_p2tr(),
+                CommitmentMethod::Opret => out.script_pubkey.is_op_return(),
+            })?;
+            Some(WitnessCommitment { method, output: output as u32 })
+        }
     }
 
     impl RgbSealDef for WTxoSeal {
         type Src = TxoSeal;
+        type ParseError = TxoSealParseError;
+
+        fn auth_token(&self) -> AuthToken { AuthTokenVersion::V0.derive(self.commit_id().to_byte_array()) }
 
-        // SECURITY: Here we cut SHA256 tagged hash of a single-use seal definition to 30 bytes in order
-        // to fit it into a field element with no overflows. This must be a secure operation since we
-        // still have a sufficient 120-bit collision resistance.
-        fn auth_token(&self) -> AuthToken {
-            let id = self.commit_id().to_byte_array();
-            let mut shortened_id = [0u8; 30];
-            shortened_id.copy_from_slice(&id[0..30]);
-            AuthToken::from_byte_array(shortened_id)
+        fn parse(s: &str) -> Result<Self, TxoSealParseError> {
+            let (primary, secondary) = parse_txo_def(s)?;
+            Ok(WTxoSeal { primary, secondary })
         }
 
         fn resolve(
@@ -125,17 +340,87 @@ pub mod bitcoin {
             };
             Some(TxoSeal { primary, secondary: self.secondary })
         }
+
+        fn is_closable(&self, witness: &Tx) -> bool {
+            match self.primary {
+                WOutpoint::Wout(wout) => witness
+                    .outputs
+                    .get(wout.to_u32() as usize)
+                    .is_some_and(|out| !out.script_pubkey.is_op_return()),
+                WOutpoint::Extern(_) => true,
+            }
+        }
+    }
+
+    /// Tag used for deriving noise of the blinded seal definitions from a wallet seed (see
+    /// [`SealBlinding`]).
+    pub const SEAL_BLINDING_TAG: &str = "urn:lnp-bp:rgb:seal-blinding#2025-06-10";
+
+    /// Deterministic source of blinding for seal definitions, derived from a wallet seed.
+    ///
+    /// The noise of each seal definition is derived from the seed, the definition index and its
+    /// primary outpoint. Thus, the definitions are unlinkable for anybody not knowing the seed,
+    /// while the wallet can recover them from a backup knowing just the seed and the range of the
+    /// used indexes.
+    #[derive(Clone)]
+    pub struct SealBlinding(Sha256);
+
+    impl SealBlinding {
+        /// Constructs the blinding source from a wallet seed.
+        pub fn new(seed: impl AsRef<[u8]>) -> Self {
+            // The seed is hashed first, so the engine input doesn't depend on the seed length.
+            let key = Sha256::from_tag(SEAL_BLINDING_TAG)
+                .with_raw(seed.as_ref())
+                .finish();
+            Self(Sha256::from_tag(SEAL_BLINDING_TAG).with_raw(&key))
+        }
+
+        /// Derives a blinded seal definition with the given primary outpoint and index.
+        pub fn seal(&self, primary: WOutpoint, index: u64) -> WTxoSeal {
+            WTxoSeal {
+                primary,
+                secondary: TxoSealExt::Noise(Noise::with(primary, self.0.clone(), index)),
+            }
+        }
+
+        /// Derives an auth token of a blinded seal definition with the given primary outpoint and
+        /// index.
+        pub fn auth_token(&self, primary: WOutpoint, index: u64) -> AuthToken { self.seal(primary, index).auth_token() }
+
+        /// Recovers a blinded seal definition with the given primary outpoint from its auth token,
+        /// trying each of the provided `indexes`.
+        ///
+        /// Returns the index and the seal definition, or `None` if none of the indexes produces the
+        /// `token`.
+        pub fn recover(
+            &self,
+            primary: WOutpoint,
+            token: AuthToken,
+            indexes: impl IntoIterator<Item = u64>,
+        ) -> Option<(u64, WTxoSeal)> {
+            indexes
+                .into_iter()
+                .map(|index| (index, self.seal(primary, index)))
+                .find(|(_, seal)| seal.auth_token() == token)
+        }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "bitcoin"))]
 mod tests {
     #![cfg_attr(coverage_nightly, coverage(off))]
 
+    use core::str::FromStr;
+
+    use amplify::num::u256;
     use amplify::ByteArray;
-    use bp::seals::{TxoSealExt, WOutpoint, WTxoSeal};
-    use bp::{Outpoint, Txid};
+    use bp::seals::{Anchor, TxoSeal, TxoSealExt, WOutpoint, WTxoSeal};
+    use bp::{Outpoint, Sats, ScriptPubkey, Tx, TxOut, Txid};
+    use commit_verify::{CommitId, DigestExt, Sha256};
+    use strict_encoding::StrictDumb;
 
+    use super::bitcoin::SealBlinding;
+    use super::txo::TxoSealParseError;
     use super::*;
 
     #[test]
@@ -147,6 +432,24 @@ mod tests {
         assert_eq!(seal.auth_token().to_string(), "at:lIIfSD7P-RQi0r3kA-7gZdmE7Q-S66QSwzG-NCxNnh7V-225u4Q");
     }
 
+    #[test]
+    fn auth_token_version() {
+        let seal = WTxoSeal::strict_dumb();
+        let id = seal.commit_id().to_byte_array();
+        assert_eq!(AuthTokenVersion::default().derive(id), seal.auth_token());
+        assert_ne!(AuthTokenVersion::V1.derive(id), seal.auth_token());
+
+        let mut small = [0u8; 32];
+        small[0] = 0xAD;
+        small[30] = 0x01;
+        assert_eq!(AuthTokenVersion::V1.derive(small).to_fe256(), fe256::from(small));
+        assert_ne!(AuthTokenVersion::V0.derive(small), AuthTokenVersion::V1.derive(small));
+
+        let max = AuthTokenVersion::V1.derive([0xFF; 32]).to_fe256().to_u256();
+        assert!(max < FIELD_ORDER_SECP);
+        assert_eq!(max + FIELD_ORDER_SECP, u256::MAX);
+    }
+
     #[test]
     fn resolve() {
         let seal = WTxoSeal {
@@ -175,4 +478,141 @@ mod tests {
         let resolved_seal = seal.resolve(txid);
         assert_eq!(seal.to_src(), Some(resolved_seal));
     }
+
+    #[test]
+    fn fallback() {
+        let outpoint = Outpoint::new(Txid::from_byte_array([0xAD; 32]), 1);
+        let seal = TxoSeal {
+            primary: Outpoint::coinbase(),
+            secondary: TxoSealExt::Fallback(outpoint),
+        };
+        assert_eq!(seal.fallback().unwrap().primary, outpoint);
+        let seal = TxoSeal { primary: outpoint, ..seal };
+        assert_eq!(seal.fallback(), None);
+        let seal = TxoSeal { secondary: TxoSealExt::strict_dumb(), ..seal };
+        assert_eq!(seal.fallback(), None);
+    }
+
+    #[test]
+    fn commitment() {
+        let mut witness = SealWitness::<TxoSeal>::new(Tx::strict_dumb(), Anchor::strict_dumb());
+        let output = |script_pubkey| TxOut { value: Sats::ZERO, script_pubkey };
+        witness
+            .published
+            .outputs
+            .push(output(ScriptPubkey::new()))
+            .unwrap();
+        assert_eq!(TxoSeal::commitment(&witness), None);
+
+        witness
+            .published
+            .outputs
+            .push(output(ScriptPubkey::op_return(&[])))
+            .unwrap();
+        let commitment = TxoSeal::commitment(&witness).unwrap();
+        assert_eq!(commitment, WitnessCommitment { method: CommitmentMethod::Opret, output: 1 });
+        assert_eq!(commitment.to_string(), "opret:1");
+
+        witness.client.dbc_proof = Some(strict_dumb!());
+        assert_eq!(TxoSeal::commitment(&witness), None);
+
+        // Each method uses the first output of its own kind, whichever comes first
+        let mut p2tr = vec![0x51, 0x20];
+        p2tr.extend([0x02; 32]);
+        witness
+            .published
+            .outputs
+            .push(output(ScriptPubkey::from_checked(p2tr)))
+            .unwrap();
+        assert_eq!(
+            TxoSeal::commitment(&witness),
+            Some(WitnessCommitment { method: CommitmentMethod::Tapret, output: 2 })
+        );
+        witness.client.dbc_proof = None;
+        assert_eq!(
+            TxoSeal::commitment(&witness),
+            Some(WitnessCommitment { method: CommitmentMethod::Opret, output: 1 })
+        );
+    }
+
+    #[test]
+    fn is_closable() {
+        let seal = WTxoSeal {
+            primary: WOutpoint::Wout(1u32.into()),
+            secondary: TxoSealExt::Fallback(Outpoint::coinbase()),
+        };
+        let mut witness = Tx::strict_dumb();
+        let output = |script_pubkey| TxOut { value: Sats::ZERO, script_pubkey };
+        witness.outputs.push(output(ScriptPubkey::new())).unwrap();
+        assert!(!seal.is_closable(&witness));
+        witness
+            .outputs
+            .push(output(ScriptPubkey::op_return(&[])))
+            .unwrap();
+        assert!(!seal.is_closable(&witness));
+        witness.outputs[1] = output(ScriptPubkey::new());
+        assert!(seal.is_closable(&witness));
+
+        let seal = WTxoSeal {
+            primary: WOutpoint::Extern(Outpoint::coinbase()),
+            secondary: TxoSealExt::Fallback(Outpoint::coinbase()),
+        };
+        assert!(seal.is_closable(&Tx::strict_dumb()));
+    }
+
+    #[test]
+    fn parse_roundtrip() {
+        let blinding = SealBlinding::new(b"wallet seed");
+        for index in 0..64u64 {
+            let txid = Txid::from_byte_array(
+                Sha256::from_tag("txid")
+                    .with_raw(&index.to_be_bytes())
+                    .finish(),
+            );
+            let outpoint = Outpoint::new(txid, index as u32 * 7);
+            for primary in [WOutpoint::Wout((index as u32).into()), WOutpoint::Extern(outpoint)] {
+                let noise = blinding.seal(primary, index).secondary;
+                for secondary in [noise, TxoSealExt::Fallback(outpoint), TxoSealExt::strict_dumb()] {
+                    let seal = WTxoSeal { primary, secondary };
+                    assert_eq!(WTxoSeal::parse(&seal.to_string()), Ok(seal));
+                    let token = seal.auth_token();
+                    assert_eq!(AuthToken::from_str(&token.to_string()).unwrap(), token);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let seal = WTxoSeal {
+            primary: WOutpoint::Wout(1u32.into()),
+            secondary: TxoSealExt::strict_dumb(),
+        };
+        let s = seal.to_string();
+        assert!(matches!(WTxoSeal::parse(s.trim_start_matches("~:")), Err(TxoSealParseError::Outpoint(_))));
+        assert!(matches!(WTxoSeal::parse(&s.replace('/', "")), Err(TxoSealParseError::NoSeparator(_))));
+        assert!(matches!(WTxoSeal::parse(&s.replace("~:1", "~:x")), Err(TxoSealParseError::Wout(_))));
+        assert!(matches!(WTxoSeal::parse(&s[..s.len() - 2]), Err(TxoSealParseError::Noise(_))));
+        assert!(matches!(WTxoSeal::parse("~:1/00:1"), Err(TxoSealParseError::Outpoint(_))));
+    }
+
+    #[test]
+    fn blinding() {
+        let wout = WOutpoint::Wout(1u32.into());
+        let blinding = SealBlinding::new(b"wallet seed");
+        let seal = blinding.seal(wout, 7);
+        assert_eq!(seal.primary, wout);
+        assert!(matches!(seal.secondary, TxoSealExt::Noise(_)));
+        assert_eq!(SealBlinding::new(b"wallet seed").seal(wout, 7), seal);
+        assert_eq!(blinding.auth_token(wout, 7), seal.auth_token());
+
+        assert_ne!(blinding.seal(wout, 8), seal);
+        assert_ne!(blinding.seal(WOutpoint::Wout(2u32.into()), 7).secondary, seal.secondary);
+        assert_ne!(SealBlinding::new(b"other seed").seal(wout, 7), seal);
+
+        assert_eq!(blinding.recover(wout, seal.auth_token(), 0..100), Some((7, seal)));
+        assert_eq!(blinding.recover(wout, seal.auth_token(), 8..100), None);
+        let extern_wout = WOutpoint::Extern(Outpoint::coinbase());
+        assert_eq!(blinding.recover(extern_wout, seal.auth_token(), 0..100), None);
+    }
 }
@@ -0,0 +1,541 @@
+// RGB Core Library: consensus layer for RGB smart contracts.
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+// Designed in 2019-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+// Written in 2024-2025 by Dr Maxim Orlovsky <orlovsky@lnp-bp.org>
+//
+// Copyright (C) 2019-2024 LNP/BP Standards Association, Switzerland.
+// Copyright (C) 2024-2025 LNP/BP Laboratories,
+//                         Institute for Distributed and Cognitive Systems (InDCS), Switzerland.
+// Copyright (C) 2025 RGB Consortium, Switzerland.
+// Copyright (C) 2019-2025 Dr Maxim Orlovsky.
+// All rights under the above copyrights are reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License"); you may not use this file except
+// in compliance with the License. You may obtain a copy of the License at
+//
+//        http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software distributed under the License
+// is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express
+// or implied. See the License for the specific language governing permissions and limitations under
+// the License.
+
+//! Adapters and combinators for consignment stream readers implementing [`ReadOperation`].
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::error::Error;
+use core::iter;
+
+use ultrasonic::Opid;
+
+use crate::verify::genesis_from_item;
+use crate::{GenesisSeals, OperationSeals, ReadOperation, RgbSeal, StreamItem};
+
+/// Consumer which writes stream items, for instance, to persist a consignment.
+pub trait WriteOperation {
+    /// Seal definition type used by operations.
+    type Seal: RgbSeal;
+
+    /// Writes a single item of a consignment stream.
+    fn write_item(&mut self, item: &StreamItem<Self::Seal>) -> Result<(), impl Error + 'static>;
+
+    /// Writes contract genesis, which must precede all other items.
+    ///
+    /// The default implementation writes the genesis as an operation in the legacy stream encoding
+    /// (see [`GenesisSeals::to_operation_seals`]).
+    fn write_genesis(&mut self, genesis: &GenesisSeals<Self::Seal>) -> Result<(), impl Error + 'static> {
+        self.write_item(&StreamItem::Operation(genesis.to_operation_seals()))
+            .map_err(StreamError::new)
+    }
+}
+
+impl<Seal: RgbSeal> WriteOperation for Vec<StreamItem<Seal>>
+where
+    Seal::PubWitness: Clone,
+    Seal::CliWitness: Clone,
+{
+    type Seal = Seal;
+
+    fn write_item(&mut self, item: &StreamItem<Self::Seal>) -> Result<(), impl Error + 'static> {
+        self.push(item.clone());
+        Ok::<_, Infallible>(())
+    }
+}
+
+/// Reads the next full operation from a reader, skipping all other item types.
+fn next_operation<R: ReadOperation>(reader: &mut R) -> Result<Option<OperationSeals<R::Seal>>, StreamError> {
+    loop {
+        match reader.read_item().map_err(StreamError::new)? {
+            None => return Ok(None),
+            Some(StreamItem::Operation(op)) => return Ok(Some(op)),
+            Some(_) => {}
+        }
+    }
+}
+
+/// Returns operation id which the stream item relates to.
+fn item_opid<Seal: RgbSeal>(item: &StreamItem<Seal>) -> Opid {
+    match item {
+        // NB: For the genesis in the legacy stream encoding this is not a real genesis opid, since
+        // its `contract_id` field holds a codex id.
+        StreamItem::Operation(op) => op.operation.opid(),
+        StreamItem::Witness(opid, _) | StreamItem::Seals(opid, _) => *opid,
+    }
+}
+
+impl<R: ReadOperation> ReadOperation for &mut R {
+    type Seal = R::Seal;
+
+    fn read_genesis(&mut self) -> Result<Option<GenesisSeals<Self::Seal>>, impl Error + 'static> {
+        (**self).read_genesis()
+    }
+
+    fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+        (**self).read_operation()
+    }
+
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> { (**self).read_item() }
+}
+
+/// Consignment stream reader over an iterator of stream items.
+#[derive(Clone, Debug)]
+pub struct IterReader<I>(I);
+
+impl<Seal: RgbSeal, I: Iterator<Item = StreamItem<Seal>>> IterReader<I> {
+    /// Constructs a reader from an iterator over stream items.
+    pub fn new(iter: impl IntoIterator<IntoIter = I>) -> Self { Self(iter.into_iter()) }
+
+    /// Releases the underlying iterator.
+    pub fn into_inner(self) -> I { self.0 }
+}
+
+impl<Seal: RgbSeal, I: Iterator<Item = OperationSeals<Seal>>>
+    IterReader<iter::Map<I, fn(OperationSeals<Seal>) -> StreamItem<Seal>>>
+{
+    /// Constructs a reader from an iterator over full operations.
+    pub fn with_operations(iter: impl IntoIterator<IntoIter = I>) -> Self {
+        Self(iter.into_iter().map(StreamItem::Operation))
+    }
+}
+
+impl<Seal: RgbSeal, I: Iterator<Item = StreamItem<Seal>>> ReadOperation for IterReader<I> {
+    type Seal = Seal;
+
+    fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+        Ok::<_, Infallible>(self.0.find_map(|item| match item {
+            StreamItem::Operation(op) => Some(op),
+            _ => None,
+        }))
+    }
+
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+        Ok::<_, Infallible>(self.0.next())
+    }
+}
+
+/// Reader which reads all items from the first reader, and then all items from the second one.
+#[derive(Clone, Debug)]
+pub struct Chain<A, B> {
+    first: Option<A>,
+    second: B,
+}
+
+impl<A, B> Chain<A, B> {
+    /// Constructs chained reader.
+    pub fn new(first: A, second: B) -> Self { Self { first: Some(first), second } }
+}
+
+impl<A: ReadOperation, B: ReadOperation<Seal = A::Seal>> ReadOperation for Chain<A, B> {
+    type Seal = A::Seal;
+
+    fn read_genesis(&mut self) -> Result<Option<GenesisSeals<Self::Seal>>, impl Error + 'static> {
+        if let Some(first) = &mut self.first {
+            match first.read_genesis().map_err(StreamError::new) {
+                Ok(None) => self.first = None,
+                res => return res,
+            }
+        }
+        self.second.read_genesis().map_err(StreamError::new)
+    }
+
+    fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+        next_operation(self)
+    }
+
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+        if let Some(first) = &mut self.first {
+            match first.read_item().map_err(StreamError::new) {
+                Ok(None) => self.first = None,
+                res => return res,
+            }
+        }
+        self.second.read_item().map_err(StreamError::new)
+    }
+}
+
+/// Reader which writes every read item into a writer before returning it.
+///
+/// # Nota bene
+///
+/// Items are written before they are verified; if the verification of the stream fails, the
+/// writer will contain a part of the consignment and the data written must be discarded.
+#[derive(Clone, Debug)]
+pub struct Tee<R, W> {
+    reader: R,
+    writer: W,
+}
+
+impl<R, W> Tee<R, W> {
+    /// Constructs a reader which writes all read items into the `writer`.
+    pub fn new(reader: R, writer: W) -> Self { Self { reader, writer } }
+
+    /// Returns a reference to the writer.
+    pub fn writer(&self) -> &W { &self.writer }
+
+    /// Releases the underlying reader and writer.
+    pub fn into_inner(self) -> (R, W) { (self.reader, self.writer) }
+}
+
+impl<R: ReadOperation, W: WriteOperation<Seal = R::Seal>> ReadOperation for Tee<R, W> {
+    type Seal = R::Seal;
+
+    fn read_genesis(&mut self) -> Result<Option<GenesisSeals<Self::Seal>>, impl Error + 'static> {
+        let genesis = match self.reader.read_genesis() {
+            Ok(genesis) => genesis,
+            Err(e) => return Err(StreamError::new(e)),
+        };
+        if let Some(genesis) = &genesis {
+            if let Err(e) = self.writer.write_genesis(genesis) {
+                return Err(StreamError::new(e));
+            }
+        }
+        Ok(genesis)
+    }
+
+    fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+        next_operation(self)
+    }
+
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+        let item = match self.reader.read_item() {
+            Ok(item) => item,
+            Err(e) => return Err(StreamError::new(e)),
+        };
+        if let Some(item) = &item {
+            if let Err(e) = self.writer.write_item(item) {
+                return Err(StreamError::new(e));
+            }
+        }
+        Ok(item)
+    }
+}
+
+/// Reader which yields only those items which relate to operations matching a predicate.
+#[derive(Clone, Debug)]
+pub struct Filter<R, F> {
+    reader: R,
+    predicate: F,
+}
+
+impl<R, F: FnMut(Opid) -> bool> Filter<R, F> {
+    /// Constructs a reader filtering stream items with the `predicate` taking the operation id.
+    ///
+    /// The genesis read with [`ReadOperation::read_genesis`] is never filtered out. Please note
+    /// that for the genesis read as an item in the legacy stream encoding the predicate
+    /// receives an operation id computed with the codex id in place of the contract id.
+    pub fn new(reader: R, predicate: F) -> Self { Self { reader, predicate } }
+
+    /// Releases the underlying reader.
+    pub fn into_inner(self) -> R { self.reader }
+}
+
+impl<R: ReadOperation, F: FnMut(Opid) -> bool> ReadOperation for Filter<R, F> {
+    type Seal = R::Seal;
+
+    fn read_genesis(&mut self) -> Result<Option<GenesisSeals<Self::Seal>>, impl Error + 'static> {
+        self.reader.read_genesis()
+    }
+
+    fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+        next_operation(self)
+    }
+
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+        loop {
+            match self.reader.read_item().map_err(StreamError::new) {
+                Ok(Some(item)) if !(self.predicate)(item_opid(&item)) => {}
+                res => return res,
+            }
+        }
+    }
+}
+
+/// Reader which counts the number of read items.
+#[derive(Clone, Debug)]
+pub struct Counting<R> {
+    reader: R,
+    count: usize,
+}
+
+impl<R> Counting<R> {
+    /// Constructs a counting reader.
+    pub fn new(reader: R) -> Self { Self { reader, count: 0 } }
+
+    /// Returns the number of items, including genesis, read so far.
+    pub fn count(&self) -> usize { self.count }
+
+    /// Releases the underlying reader.
+    pub fn into_inner(self) -> R { self.reader }
+}
+
+impl<R: ReadOperation> ReadOperation for Counting<R> {
+    type Seal = R::Seal;
+
+    fn read_genesis(&mut self) -> Result<Option<GenesisSeals<Self::Seal>>, impl Error + 'static> {
+        let genesis = self.reader.read_genesis();
+        if let Ok(Some(_)) = genesis {
+            self.count += 1;
+        }
+        genesis
+    }
+
+    fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+        next_operation(self)
+    }
+
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+        let item = self.reader.read_item();
+        if let Ok(Some(_)) = item {
+            self.count += 1;
+        }
+        item
+    }
+}
+
+/// Reader which allows to look at the next item without consuming it.
+pub struct Peekable<R: ReadOperation> {
+    reader: R,
+    peeked: Option<Option<StreamItem<R::Seal>>>,
+}
+
+impl<R: ReadOperation> Peekable<R> {
+    /// Constructs a peekable reader.
+    pub fn new(reader: R) -> Self { Self { reader, peeked: None } }
+
+    /// Returns a reference to the next item without advancing the reader.
+    ///
+    /// Returns `None` if the stream is over.
+    pub fn peek(&mut self) -> Result<Option<&StreamItem<R::Seal>>, StreamError> {
+        if self.peeked.is_none() {
+            let item = self.reader.read_item().map_err(StreamError::new)?;
+            self.peeked = Some(item);
+        }
+        Ok(self.peeked.as_ref().and_then(Option::as_ref))
+    }
+}
+
+impl<R: ReadOperation> ReadOperation for Peekable<R> {
+    type Seal = R::Seal;
+
+    fn read_genesis(&mut self) -> Result<Option<GenesisSeals<Self::Seal>>, impl Error + 'static> {
+        match self.peeked.take() {
+            Some(item) => genesis_from_item(item).map_err(StreamError::new),
+            None => self.reader.read_genesis().map_err(StreamError::new),
+        }
+    }
+
+    fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+        next_operation(self)
+    }
+
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+        match self.peeked.take() {
+            Some(item) => Ok(item),
+            None => self.reader.read_item().map_err(StreamError::new),
+        }
+    }
+}
+
+/// Reader which provides a genesis which is not a part of the underlying stream.
+pub struct WithGenesis<R: ReadOperation> {
+    reader: R,
+    genesis: Option<GenesisSeals<R::Seal>>,
+}
+
+impl<R: ReadOperation> WithGenesis<R> {
+    /// Constructs a reader returning `genesis` before the items of the `reader`.
+    pub fn new(reader: R, genesis: GenesisSeals<R::Seal>) -> Self { Self { reader, genesis: Some(genesis) } }
+
+    /// Releases the underlying reader.
+    pub fn into_inner(self) -> R { self.reader }
+}
+
+impl<R: ReadOperation> ReadOperation for WithGenesis<R> {
+    type Seal = R::Seal;
+
+    fn read_genesis(&mut self) -> Result<Option<GenesisSeals<Self::Seal>>, impl Error + 'static> {
+        Ok::<_, Infallible>(self.genesis.take())
+    }
+
+    fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+        next_operation(self)
+    }
+
+    /// If the genesis was not read yet, returns it as the first item in the legacy stream encoding.
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+        match self.genesis.take() {
+            Some(genesis) => Ok(Some(StreamItem::Operation(genesis.to_operation_seals()))),
+            None => self.reader.read_item().map_err(StreamError::new),
+        }
+    }
+}
+
+/// Extension trait providing combinators for all [`ReadOperation`] implementations.
+pub trait ReadOperationExt: ReadOperation {
+    /// Reads all items from this reader, and then all items from the `other` one.
+    fn chain<B: ReadOperation<Seal = Self::Seal>>(self, other: B) -> Chain<Self, B> { Chain::new(self, other) }
+
+    /// Writes each read item into the `writer`.
+    fn tee<W: WriteOperation<Seal = Self::Seal>>(self, writer: W) -> Tee<Self, W> { Tee::new(self, writer) }
+
+    /// Yields only items related to operations with ids matching the `predicate`.
+    fn filter_opid<F: FnMut(Opid) -> bool>(self, predicate: F) -> Filter<Self, F> { Filter::new(self, predicate) }
+
+    /// Counts the number of read items.
+    fn counting(self) -> Counting<Self> { Counting::new(self) }
+
+    /// Allows to peek the next item without consuming it.
+    fn peekable(self) -> Peekable<Self> { Peekable::new(self) }
+
+    /// Provides the `genesis` which is not a part of this reader stream.
+    fn with_genesis(self, genesis: GenesisSeals<Self::Seal>) -> WithGenesis<Self> { WithGenesis::new(self, genesis) }
+}
+
+impl<R: ReadOperation> ReadOperationExt for R {}
+
+/// Error returned by stream adapters, wrapping an error of the underlying reader or writer.
+#[derive(Debug, Display)]
+#[display(inner)]
+pub struct StreamError(Box<dyn Error>);
+
+impl StreamError {
+    /// Wraps an error returned by a reader or a writer.
+    pub fn new(err: impl Error + 'static) -> Self { Self(Box::new(err)) }
+}
+
+impl Error for StreamError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> { Some(&*self.0) }
+}
+
+#[cfg(all(test, feature = "bitcoin"))]
+mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use std::vec;
+
+    use bp::seals::TxoSeal;
+    use strict_encoding::StrictDumb;
+    use ultrasonic::{fe256, Operation};
+
+    use super::*;
+
+    fn op(nonce: u64) -> OperationSeals<TxoSeal> {
+        let mut operation = Operation::strict_dumb();
+        operation.nonce = fe256::from(nonce);
+        OperationSeals { operation, defined_seals: none!(), witness: None }
+    }
+
+    fn ops(nonces: impl IntoIterator<Item = u64>) -> Vec<OperationSeals<TxoSeal>> {
+        nonces.into_iter().map(op).collect()
+    }
+
+    fn nonces(mut reader: impl ReadOperation<Seal = TxoSeal>) -> Vec<u64> {
+        let mut nonces = vec![];
+        while let Some(op) = reader.read_operation().unwrap() {
+            nonces.push(op.operation.nonce.to_u256().low_u64());
+        }
+        nonces
+    }
+
+    #[test]
+    fn iter() {
+        let reader = IterReader::with_operations(ops(0..3));
+        assert_eq!(nonces(reader), vec![0, 1, 2]);
+
+        let witness_item = StreamItem::Witness(Opid::strict_dumb(), strict_dumb!());
+        let mut reader = IterReader::new(vec![witness_item, op(0).into()]);
+        assert!(matches!(reader.read_item().unwrap(), Some(StreamItem::Witness(..))));
+        assert!(matches!(reader.read_item().unwrap(), Some(StreamItem::Operation(..))));
+        assert!(reader.read_item().unwrap().is_none());
+    }
+
+    #[test]
+    fn chain() {
+        let reader = IterReader::with_operations(ops(0..2)).chain(IterReader::with_operations(ops(5..7)));
+        assert_eq!(nonces(reader), vec![0, 1, 5, 6]);
+    }
+
+    #[test]
+    fn tee() {
+        let mut reader = IterReader::with_operations(ops(0..3)).tee(vec![]);
+        reader.read_item().unwrap();
+        reader.read_item().unwrap();
+        assert_eq!(reader.writer().len(), 2);
+        let (_, written) = reader.into_inner();
+        assert_eq!(nonces(IterReader::new(written)), vec![0, 1]);
+    }
+
+    #[test]
+    fn filter() {
+        let skip = op(1).operation.opid();
+        let reader = IterReader::with_operations(ops(0..4)).filter_opid(|opid| opid != skip);
+        assert_eq!(nonces(reader), vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn counting() {
+        let mut reader = IterReader::with_operations(ops(0..3)).counting();
+        assert_eq!(reader.count(), 0);
+        reader.read_operation().unwrap();
+        assert_eq!(reader.count(), 1);
+        while reader.read_item().unwrap().is_some() {}
+        assert_eq!(reader.count(), 3);
+    }
+
+    #[test]
+    fn peekable() {
+        let mut reader = IterReader::with_operations(ops(0..2)).peekable();
+        let peeked = reader.peek().unwrap().map(item_opid);
+        assert_eq!(peeked, Some(op(0).operation.opid()));
+        assert_eq!(reader.peek().unwrap().map(item_opid), peeked);
+        assert_eq!(nonces(&mut reader), vec![0, 1]);
+        assert!(reader.peek().unwrap().is_none());
+    }
+
+    #[test]
+    fn with_genesis() {
+        let genesis = GenesisSeals::<TxoSeal>::strict_dumb();
+        let mut reader = IterReader::with_operations(ops(0..2))
+            .with_genesis(genesis.clone())
+            .tee(vec![]);
+        assert_eq!(reader.read_genesis().unwrap(), Some(genesis.clone()));
+        assert_eq!(nonces(&mut reader), vec![0, 1]);
+
+        // The genesis is written in the legacy stream encoding and can be read back
+        let (_, written) = reader.into_inner();
+        let mut reader = IterReader::new(written);
+        assert_eq!(reader.read_genesis().unwrap(), Some(genesis));
+        assert_eq!(nonces(reader), vec![0, 1]);
+    }
+
+    #[test]
+    fn error_source() {
+        let err = StreamError::new(std::io::Error::other("reader failure"));
+        assert_eq!(err.to_string(), "reader failure");
+        assert_eq!(err.source().unwrap().to_string(), "reader failure");
+    }
+}
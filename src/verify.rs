@@ -31,10 +31,12 @@ use amplify::confinement::SmallOrdMap;
 use amplify::ByteArray;
 use single_use_seals::{PublishedWitness, SealError, SealWitness};
 use ultrasonic::{
-    AuthToken, CallError, CellAddr, Codex, ContractId, LibRepo, Memory, Operation, Opid, VerifiedOperation,
+    AuthToken, CallError, CallId, CellAddr, Codex, CodexId, ContractId, ContractMeta, Genesis, Issue, LibRepo, Memory,
+    Operation, Opid, StateCell, StateValue, VerifiedOperation,
 };
 
-use crate::{RgbSeal, RgbSealDef, LIB_NAME_RGB};
+use crate::stream::StreamError;
+use crate::{RgbSeal, RgbSealDef, WitnessCommitment, LIB_NAME_RGB};
 
 /// Combination of an operation with operation-defined seals.
 ///
@@ -75,6 +77,135 @@ where
     }
 }
 
+/// Contract genesis with the seals it defines.
+///
+/// Unlike other operations, genesis doesn't commit to a contract id, since the contract id is
+/// derived from the genesis itself; hence it is read from the consignment stream as a separate
+/// record (see [`ReadOperation::read_genesis`]).
+#[derive(Clone, Eq, PartialEq, Debug)]
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "camelCase", bound = "Seal::Definition: serde::Serialize + for<'d> serde::Deserialize<'d>")
+)]
+pub struct GenesisSeals<Seal: RgbSeal> {
+    /// The contract genesis.
+    pub genesis: Genesis,
+    /// Seals defined by the genesis.
+    pub defined_seals: SmallOrdMap<u16, Seal::Definition>,
+}
+
+impl<Seal: RgbSeal> GenesisSeals<Seal> {
+    /// Converts the genesis into an operation in the legacy stream encoding, where the operation
+    /// `contract_id` field holds the codex id.
+    pub fn to_operation_seals(&self) -> OperationSeals<Seal> {
+        let codex_id = ContractId::from_byte_array(self.genesis.codex_id.to_byte_array());
+        OperationSeals {
+            operation: self.genesis.to_operation(codex_id),
+            defined_seals: self.defined_seals.clone(),
+            witness: None,
+        }
+    }
+}
+
+impl<Seal: RgbSeal> TryFrom<OperationSeals<Seal>> for GenesisSeals<Seal> {
+    type Error = GenesisError;
+
+    /// Converts an operation in the legacy stream encoding, where the operation `contract_id` field
+    /// holds the codex id, into a genesis.
+    fn try_from(block: OperationSeals<Seal>) -> Result<Self, Self::Error> {
+        let op = block.operation;
+        if !op.destructible_in.is_empty() || !op.immutable_in.is_empty() || op.witness != StateValue::None {
+            return Err(GenesisError::Inputs);
+        }
+        if block.witness.is_some() {
+            return Err(GenesisError::Witness);
+        }
+        let genesis = Genesis {
+            version: op.version,
+            codex_id: CodexId::from_byte_array(op.contract_id.to_byte_array()),
+            call_id: op.call_id,
+            nonce: op.nonce,
+            blank0: default!(),
+            blank1: default!(),
+            blank2: default!(),
+            destructible_out: op.destructible_out,
+            immutable_out: op.immutable_out,
+        };
+        Ok(Self { genesis, defined_seals: block.defined_seals })
+    }
+}
+
+/// Errors reading contract genesis from a consignment stream.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Display, Error)]
+#[display(doc_comments)]
+pub enum GenesisError {
+    /// consignment stream doesn't start with a genesis operation.
+    NotOperation,
+
+    /// genesis must not have inputs or an operation witness.
+    Inputs,
+
+    /// genesis must not have a seal closing witness.
+    Witness,
+}
+
+/// A typed item of a consignment stream.
+///
+/// Apart from full operations, a stream may contain updates to the operations which were already
+/// present in the same stream before: additional (for instance, replacement) witnesses and late
+/// reveals of the seal definitions. These updates do not repeat the operation body.
+#[derive(StrictType, StrictDumb, StrictEncode, StrictDecode)]
+#[strict_type(lib = LIB_NAME_RGB, tags = custom, dumb = Self::Operation(strict_dumb!()))]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(
+        rename_all = "camelCase",
+        bound = "Seal::Definition: serde::Serialize + for<'d> serde::Deserialize<'d>, Seal::PubWitness: \
+                 serde::Serialize + for<'d> serde::Deserialize<'d>, Seal::CliWitness: serde::Serialize + for<'d> \
+                 serde::Deserialize<'d>"
+    )
+)]
+#[allow(clippy::large_enum_variant)]
+pub enum StreamItem<Seal: RgbSeal> {
+    /// A full operation with its seal definitions and an optional witness.
+    #[strict_type(tag = 0)]
+    Operation(OperationSeals<Seal>),
+
+    /// A witness for an operation which was already read from the stream.
+    ///
+    /// The witness is verified to close the same seals as the operation and, if valid, replaces
+    /// previously known witness-output-based seals defined by the operation.
+    #[strict_type(tag = 1)]
+    Witness(Opid, SealWitness<Seal>),
+
+    /// Late reveal of the seal definitions for outputs of an operation which was already read from
+    /// the stream.
+    #[strict_type(tag = 2)]
+    Seals(Opid, SmallOrdMap<u16, Seal::Definition>),
+}
+
+impl<Seal: RgbSeal> Clone for StreamItem<Seal>
+where
+    Seal::PubWitness: Clone,
+    Seal::CliWitness: Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Self::Operation(op) => Self::Operation(op.clone()),
+            Self::Witness(opid, witness) => Self::Witness(*opid, witness.clone()),
+            Self::Seals(opid, seals) => Self::Seals(*opid, seals.clone()),
+        }
+    }
+}
+
+impl<Seal: RgbSeal> From<OperationSeals<Seal>> for StreamItem<Seal> {
+    fn from(op: OperationSeals<Seal>) -> Self { Self::Operation(op) }
+}
+
 /// Provider which reads an operation and its seals from a consignment stream.
 pub trait ReadOperation: Sized {
     /// Seal definition type used by operations.
@@ -83,18 +214,64 @@ pub trait ReadOperation: Sized {
     /// Reads an operation and its seals from a consignment stream and initialize the witness
     /// reader.
     fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static>;
+
+    /// Reads the contract genesis, which must be the first record of a consignment stream.
+    ///
+    /// Called by [`ContractVerify::evaluate`] only once, before reading other items. Returns
+    /// `None` if the stream is empty.
+    ///
+    /// The default implementation reads the first item with [`Self::read_item`] and converts it
+    /// from the legacy stream encoding, where the genesis is an operation having the codex id in
+    /// place of the contract id.
+    fn read_genesis(&mut self) -> Result<Option<GenesisSeals<Self::Seal>>, impl Error + 'static> {
+        match self.read_item() {
+            Ok(item) => genesis_from_item(item).map_err(StreamError::new),
+            Err(e) => Err(StreamError::new(e)),
+        }
+    }
+
+    /// Reads the next typed item from a consignment stream.
+    ///
+    /// This is the method used by [`ContractVerify::evaluate`] for all items following the
+    /// genesis. The default implementation yields
+    /// only full operations returned by [`Self::read_operation`]; readers providing witness
+    /// updates or late seal reveals must override it.
+    fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+        self.read_operation()
+            .map(|op| op.map(StreamItem::Operation))
+    }
+}
+
+/// Converts the first item of a consignment stream in the legacy encoding into a genesis.
+pub(crate) fn genesis_from_item<Seal: RgbSeal>(
+    item: Option<StreamItem<Seal>>,
+) -> Result<Option<GenesisSeals<Seal>>, GenesisError> {
+    match item {
+        None => Ok(None),
+        Some(StreamItem::Operation(block)) => GenesisSeals::try_from(block).map(Some),
+        Some(_) => Err(GenesisError::NotOperation),
+    }
 }
 
 /// API exposed by the contract required for evaluating and verifying the contract state (see
 /// [`ContractVerify`]).
 ///
-/// NB: `apply_operation` is called only after `apply_witness`.
+/// NB: `apply_operation` is called only after `apply_witness`, except for the pending operations
+/// (see [`VerifyConfig::pending`]), for which `apply_witness` is called once the witness is known.
 pub trait ContractApi<Seal: RgbSeal> {
     /// Returns contract id for the processed contract.
     ///
     /// Called only once during the operation verification.
     fn contract_id(&self) -> ContractId;
 
+    /// Returns metadata of the contract issue.
+    ///
+    /// Together with [`Self::codex`] and the genesis from the consignment stream, it is used to
+    /// check that the contract id from [`Self::contract_id`] is correct.
+    ///
+    /// Called only once during the operation verification.
+    fn meta(&self) -> &ContractMeta;
+
     /// Returns a codex against which the contract must be verified.
     ///
     /// Called only once during the operation verification.
@@ -127,14 +304,55 @@ pub trait ContractApi<Seal: RgbSeal> {
     ///
     /// The method is called for all operations, including known ones, for which the consignment
     /// provides at least single seal definition information (thus, it may be called for the genesis
-    /// operation as well).
+    /// operation as well). It is also called for each late seal reveal ([`StreamItem::Seals`]),
+    /// hence it may be called multiple times for the same operation.
     fn apply_seals(&mut self, opid: Opid, seals: SmallOrdMap<u16, Seal::Definition>);
 
+    /// Returns a seal definition known to the contract (i.e., previously provided to
+    /// [`Self::apply_seals`]) which produces the auth `token`, together with the address of the
+    /// cell it is defined for.
+    ///
+    /// Used to detect auth token collisions between the seal definitions revealed by a consignment
+    /// and the seal definitions of the whole contract (see
+    /// [`VerificationError::AuthTokenCollision`]). The default implementation returns `None`,
+    /// limiting the detection to the seal definitions revealed by the consignment itself.
+    fn seal_definition(&self, _token: AuthToken) -> Option<(CellAddr, Seal::Definition)> { None }
+
     /// # Nota bene:
     ///
     /// The method is called for all operations, including known ones, which have a witness (i.e.,
-    /// except genesis or operations with no destroyed state).
+    /// except genesis or operations with no destroyed state). It is also called for each witness
+    /// update ([`StreamItem::Witness`]), hence it may be called multiple times for the same
+    /// operation.
     fn apply_witness(&mut self, opid: Opid, witness: SealWitness<Seal>);
+
+    /// Moves an operation to the tentative or confirmed state layer.
+    ///
+    /// # Nota bene:
+    ///
+    /// The method is called by [`ContractVerify::evaluate_layered`], and in the pending mode (see
+    /// [`VerifyConfig::pending`]), for all accepted operations except genesis, after they are
+    /// applied, and each time their layer changes. Operations accepted without a witness, and their
+    /// descendants, are reported as tentative until the witness is supplied. The default
+    /// implementation does nothing.
+    fn apply_status(&mut self, _opid: Opid, _status: WitnessStatus) {}
+}
+
+/// Status of an operation witness, defining the state layer for the operation.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Display)]
+#[display(lowercase)]
+pub enum WitnessStatus {
+    /// The witness is not confirmed yet (or is not known), and may be replaced.
+    Tentative,
+    /// The witness is confirmed.
+    Confirmed,
+}
+
+/// Resolver of the witness status, used to classify the operations into the state layers (see
+/// [`ContractVerify::evaluate_layered`]).
+pub trait WitnessResolver<Seal: RgbSeal> {
+    /// Returns the status of a published witness with the given id.
+    fn witness_status(&self, witness_id: <Seal::PubWitness as PublishedWitness<Seal>>::PubId) -> WitnessStatus;
 }
 
 /// Main implementation of the contract verification procedure.
@@ -147,278 +365,1276 @@ pub trait ContractApi<Seal: RgbSeal> {
 /// The purpose of the trait is to prevent overriding of the implementation in client libraries.
 pub trait ContractVerify<Seal: RgbSeal>: ContractApi<Seal> {
     /// Evaluate contract state by verifying and applying contract operations coming from a
-    /// consignment `reader`.
-    fn evaluate<R: ReadOperation<Seal = Seal>>(&mut self, mut reader: R) -> Result<(), VerificationError<Seal>> {
-        let contract_id = self.contract_id();
-        let codex_id = self.codex().codex_id();
-
-        let mut is_genesis = true;
-        let mut seals = BTreeMap::<CellAddr, Seal>::new();
-
-        while let Some(mut block) = reader
-            .read_operation()
-            .map_err(|e| VerificationError::Stream(Box::new(e)))?
-        {
-            // Genesis cannot commit to the contract id since the contract does not exist yet;
-            // thus, we have to apply this little trick
-            if is_genesis {
-                if block.operation.contract_id.to_byte_array() != codex_id.to_byte_array() {
-                    return Err(VerificationError::NoCodexCommitment);
-                }
-                block.operation.contract_id = contract_id;
-            }
-            let opid = block.operation.opid();
-
-            // We need to check that all seal definitions strictly match operation-defined destructible cells
-            let defined = block
-                .operation
-                .destructible_out
-                .iter()
-                .map(|cell| cell.auth)
-                .collect::<BTreeSet<_>>();
-            let reported = block
-                .defined_seals
-                .values()
-                .map(|seal| seal.auth_token())
-                .collect::<BTreeSet<_>>();
-            // It is a subset and not an equal set since some seals might be unknown to us:
-            // we know their commitment auth token but do not know the definition.
-            if !reported.is_subset(&defined) {
-                let sources = block
-                    .defined_seals
-                    .iter()
-                    .map(|(pos, seal)| (*pos, seal.to_string()))
-                    .collect();
-                return Err(VerificationError::SealsDefinitionMismatch { opid, reported, defined, sources });
-            }
-
-            // Collect single-use seal closings by the operation
-            let mut closed_seals = Vec::<Seal>::new();
-            for input in &block.operation.destructible_in {
-                let seal = seals
-                    .remove(&input.addr)
-                    .ok_or(VerificationError::SealUnknown(input.addr))?;
-                closed_seals.push(seal);
-            }
-
-            // If the operation was validated before, we need to skip its validation, since its inputs are not a
-            // part of the state anymore.
-            let operation = if self.is_known(opid) {
-                None
-            } else {
-                // Verify the operation
-                let verified = self
-                    .codex()
-                    .verify(contract_id, block.operation, self.memory(), self.repo())?;
-                Some(verified)
-            };
-
-            // This convoluted logic happens since we use a state machine which ensures the client can't lie to
-            // the verifier
-            // Now we can add operation-defined seals to the set of known seals
-            let mut seal_sources: BTreeSet<_> = block
-                .defined_seals
-                .iter()
-                .filter_map(|(pos, seal)| seal.to_src().map(|seal| (CellAddr::new(opid, *pos), seal)))
-                .collect();
+    /// consignment `reader`, using the default verification configuration.
+    ///
+    /// See [`Self::evaluate_with`] for the details.
+    fn evaluate<R: ReadOperation<Seal = Seal>>(&mut self, reader: R) -> Result<(), VerificationError<Seal>> {
+        self.evaluate_with(reader, &VerifyConfig::default())
+            .map(|_| ())
+    }
 
-            if let Some(witness) = block.witness {
-                let msg = opid.to_byte_array();
-                witness
-                    .verify_seals_closing(&closed_seals, msg.into())
-                    .map_err(|e| VerificationError::SealsNotClosed(witness.published.pub_id(), opid, e))?;
+    /// Evaluate contract state by verifying and applying contract operations coming from a
+    /// consignment `reader`, using the provided verification `config`.
+    ///
+    /// The consignment stream must start with the contract genesis (see
+    /// [`ReadOperation::read_genesis`]); no other operation in the stream may claim to be a
+    /// genesis.
+    ///
+    /// Witness updates and seal reveals coming from the stream (see [`StreamItem`]) must reference
+    /// operations which were already read from the same stream.
+    fn evaluate_with<R: ReadOperation<Seal = Seal>>(
+        &mut self,
+        reader: R,
+        config: &VerifyConfig,
+    ) -> Result<EvaluationReport<Seal>, VerificationError<Seal>> {
+        evaluate(self, reader, config, None)
+    }
 
-                //  Each witness actually produces its own set of witness-output-based seal sources.
-                let pub_id = witness.published.pub_id();
-                let iter = block
-                    .defined_seals
-                    .iter()
-                    .filter(|(_, seal)| seal.to_src().is_none())
-                    .map(|(pos, seal)| (CellAddr::new(opid, *pos), seal.resolve(pub_id)));
-                seal_sources.extend(iter);
+    /// Evaluate contract state like [`Self::evaluate_with`], additionally classifying the
+    /// operations into the tentative and confirmed state layers.
+    ///
+    /// An operation is confirmed if its witness, and witnesses of all its ancestors, are confirmed
+    /// according to the `resolver`; operations having no witness since they do not close seals are
+    /// confirmed if all their ancestors are. The layer of each accepted operation (except genesis)
+    /// is reported with [`ContractApi::apply_status`] once the operation is applied, and each time
+    /// it changes due to a witness update from the stream (for instance, it is promoted to the
+    /// confirmed layer). Once the whole stream is read, the `resolver` is polled again for the
+    /// tentative witnesses, promoting the operations whose witnesses got confirmed during the
+    /// evaluation. To promote operations whose witnesses got confirmed after the evaluation,
+    /// evaluate the consignment again: known operations are re-classified and their changed layers
+    /// are reported to the contract.
+    fn evaluate_layered<R: ReadOperation<Seal = Seal>>(
+        &mut self,
+        reader: R,
+        config: &VerifyConfig,
+        resolver: &impl WitnessResolver<Seal>,
+    ) -> Result<EvaluationReport<Seal>, VerificationError<Seal>> {
+        evaluate(self, reader, config, Some(resolver))
+    }
+}
 
-                self.apply_witness(opid, witness);
-            } else if !closed_seals.is_empty() {
-                return Err(VerificationError::NoWitness(opid));
-            }
+/// Implementation of the contract verification procedure, shared by [`ContractVerify`] methods.
+fn evaluate<Seal: RgbSeal, C: ContractApi<Seal> + ?Sized, R: ReadOperation<Seal = Seal>>(
+    contract: &mut C,
+    mut reader: R,
+    config: &VerifyConfig,
+    resolver: Option<&dyn WitnessResolver<Seal>>,
+) -> Result<EvaluationReport<Seal>, VerificationError<Seal>> {
+    let contract_id = contract.contract_id();
+    let codex_id = contract.codex().codex_id();
+
+    let Some(GenesisSeals { genesis, defined_seals }) = reader
+        .read_genesis()
+        .map_err(|e| VerificationError::Stream(Box::new(e)))?
+    else {
+        return Evaluation::new(contract_id, config, resolver).complete();
+    };
+    if genesis.codex_id != codex_id {
+        return Err(VerificationError::NoCodexCommitment);
+    }
+    // We must not trust the contract id provided by the contract API and check that it is
+    // derived from the genesis we process.
+    let issue = Issue {
+        version: default!(),
+        meta: contract.meta().clone(),
+        codex: contract.codex().clone(),
+        genesis,
+    };
+    let found = issue.contract_id();
+    if found != contract_id {
+        return Err(VerificationError::ContractIdMismatch { expected: contract_id, found });
+    }
+    let operation = issue.genesis.to_operation(contract_id);
+    let genesis_opid = operation.opid();
 
-            seals.extend(seal_sources);
-            if is_genesis {
-                is_genesis = false
-            } else if let Some(operation) = operation {
-                self.apply_operation(operation);
-            }
+    if let Some(expected) = config.genesis_call {
+        if operation.call_id != expected {
+            return Err(VerificationError::GenesisCall { expected, found: operation.call_id });
+        }
+        contract
+            .codex()
+            .verify(contract_id, operation.clone(), contract.memory(), contract.repo())
+            .map_err(VerificationError::GenesisInvalid)?;
+    }
 
-            if !block.defined_seals.is_empty() {
-                self.apply_seals(opid, block.defined_seals);
+    let mut evaluation = Evaluation::new(contract_id, config, resolver);
+    let block = OperationSeals { operation, defined_seals, witness: None };
+    evaluation.operation(contract, genesis_opid, block, true)?;
+
+    while let Some(item) = reader
+        .read_item()
+        .map_err(|e| VerificationError::Stream(Box::new(e)))?
+    {
+        match item {
+            StreamItem::Operation(block) => {
+                let opid = block.operation.opid();
+                if opid == genesis_opid || block.operation.contract_id.to_byte_array() == codex_id.to_byte_array() {
+                    return Err(VerificationError::RepeatedGenesis(opid));
+                }
+                evaluation.operation(contract, opid, block, false)?;
             }
+            StreamItem::Witness(opid, witness) => evaluation.witness(contract, opid, witness)?,
+            StreamItem::Seals(opid, revealed) => evaluation.seals(contract, opid, revealed)?,
         }
-
-        Ok(())
     }
-}
 
-impl<Seal: RgbSeal, C: ContractApi<Seal>> ContractVerify<Seal> for C {}
+    evaluation.repoll(contract);
+    evaluation.complete()
+}
 
-/// Errors returned from the verification.
-#[derive(Display, Error, From)]
-#[display(doc_comments)]
-pub enum VerificationError<Seal: RgbSeal> {
-    /// error reading the consignment stream.
+/// Configuration of the contract verification procedure (see [`ContractVerify::evaluate_with`]).
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct VerifyConfig {
+    /// Call id which must be used by the genesis, if the genesis has to be verified by the codex.
     ///
-    /// Details: {0}
-    Stream(Box<dyn Error>),
-
-    /// genesis does not commit to the codex id; a wrong contract genesis is used.
-    NoCodexCommitment,
-
-    /// no witness known for the operation {0}.
-    NoWitness(Opid),
+    /// Genesis is normally known to the contract (see [`ContractApi::is_known`]) and thus is not
+    /// verified. Issuers may set this option to run the genesis through the codex verifier for the
+    /// call before accepting it, ensuring the genesis doesn't violate the codex rules.
+    pub genesis_call: Option<CallId>,
 
-    /// single-use seals are not closed properly with witness {0} for operation {1}.
+    /// Re-verify all operations from genesis, including known ones.
     ///
-    /// Details: {2}
-    SealsNotClosed(<Seal::PubWitness as PublishedWitness<Seal>>::PubId, Opid, SealError<Seal>),
+    /// In this mode [`ContractApi::is_known`] is not used to skip the verification, and operations
+    /// are verified against a fresh memory built from the consignment stream only, rather than
+    /// against [`ContractApi::memory`]. This allows auditing a contract state which might have
+    /// been produced by a faulty verifier: all known operations which don't pass the verification
+    /// are reported with [`VerificationError::KnownInvalid`] once the whole stream is processed.
+    pub paranoid: bool,
+
+    /// Auth tokens of the seals which must be defined by the accepted operations.
+    ///
+    /// Allows a payee to check that a consignment ends in the seals it controls. If any of these
+    /// seals is not defined by an unspent output of the operations from the stream, the evaluation
+    /// fails with [`VerificationError::TerminalsMissing`].
+    pub terminals: BTreeSet<AuthToken>,
 
-    /// unknown seal definition for cell address {0}.
-    SealUnknown(CellAddr),
+    /// Reject operations which are not ancestors of the operations defining [`Self::terminals`].
+    ///
+    /// Allows a payee to refuse consignments bloated with unrelated operations; such operations
+    /// are reported with [`VerificationError::Unrelated`]. Has no effect if no terminals are set.
+    ///
+    /// # Nota bene
+    ///
+    /// Since the ancestry is known only after the whole stream is read, the check happens when all
+    /// the operations, including the unrelated ones, were already applied to the contract. Thus,
+    /// the option doesn't prevent the contract state from being bloated by itself: the caller must
+    /// evaluate the consignment against a staging copy of the contract state (or otherwise roll
+    /// the applied changes back) and discard it if the evaluation fails.
+    pub ancestors_only: bool,
+
+    /// Quarantine invalid operations instead of failing the whole evaluation.
+    ///
+    /// In this mode all operations whose full ancestry is valid are accepted, while the operations
+    /// failing the verification, and all their descendants, are put into the quarantine returned
+    /// in [`EvaluationReport::quarantine`]. Witness updates and seal reveals for the quarantined
+    /// operations are added to them without verification.
+    ///
+    /// Genesis and stream-level errors (like a failure to read the stream or an item referencing
+    /// an unknown operation) still fail the evaluation.
+    pub best_effort: bool,
 
-    /// seals, reported to be defined by the operation {opid}, do match the assignments in the
-    /// operation.
+    /// Accept operations which close seals but have no witness yet.
     ///
-    /// Actual operation seals from the assignments: {defined:#?}
+    /// Such operations (for instance, prepared by us, but whose witness was not published yet) are
+    /// verified by the codex and applied to the contract, but their witness-output-based seals
+    /// remain unresolved until the witness is supplied with [`StreamItem::Witness`], at which
+    /// point the closing of the seals is checked. Operations remaining without a witness are
+    /// reported in [`EvaluationReport::pending`].
     ///
-    /// Reported seals: {reported:#?}
+    /// Such operations, and their descendants, are applied to the contract in the tentative state
+    /// layer (see [`ContractApi::apply_status`]); the contract must persist the layer, since the
+    /// report doesn't survive between evaluations.
+    pub pending: bool,
+}
+
+impl VerifyConfig {
+    /// Adds seals, which must be defined by the accepted operations, to [`Self::terminals`].
+    pub fn expect_seals<D: RgbSealDef>(mut self, seals: impl IntoIterator<Item = D>) -> Self {
+        self.terminals
+            .extend(seals.into_iter().map(|seal| seal.auth_token()));
+        self
+    }
+}
+
+/// Report on the completed contract evaluation (see [`ContractVerify::evaluate_with`]).
+pub struct EvaluationReport<Seal: RgbSeal> {
+    /// Operations which were not accepted in the best-effort mode (see
+    /// [`VerifyConfig::best_effort`]), in the order of the consignment stream.
     ///
-    /// Sources for the reported seals: {sources:#?}
-    #[allow(missing_docs)]
-    SealsDefinitionMismatch {
-        opid: Opid,
-        reported: BTreeSet<AuthToken>,
-        defined: BTreeSet<AuthToken>,
-        sources: BTreeMap<u16, String>,
-    },
+    /// To re-examine them once the missing witnesses or libraries arrive, evaluate the
+    /// consignment again, providing the quarantined operations in the stream after the accepted
+    /// ones.
+    pub quarantine: Vec<Quarantined<Seal>>,
+
+    /// Operations accepted without a witness in the pending mode (see [`VerifyConfig::pending`]),
+    /// for which no witness was supplied by the end of the stream.
+    pub pending: BTreeSet<Opid>,
+
+    /// Commitments made by the most recent verified witness of each operation, for the seal
+    /// protocols which commit through witness outputs (see [`RgbSeal::commitment`]).
+    pub commitments: BTreeMap<Opid, WitnessCommitment>,
+}
 
-    /// Eror returned by the virtual machine script.
-    #[from]
-    #[display(inner)]
-    Vm(CallError),
+/// Operation quarantined during the best-effort evaluation (see [`VerifyConfig::best_effort`]).
+pub struct Quarantined<Seal: RgbSeal> {
+    /// Id of the quarantined operation.
+    pub opid: Opid,
+    /// The quarantined operation with its seal definitions and the last witness read from the
+    /// stream.
+    pub block: OperationSeals<Seal>,
+    /// The reason for the quarantine.
+    pub reason: QuarantineReason<Seal>,
 }
 
-// We need manual implementation since otherwise we get an unneeded `Seal::PubWitness: Debug` bound
-impl<Seal: RgbSeal> Debug for VerificationError<Seal> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result { write!(f, "{}", self) }
+// We need manual implementations since `OperationSeals` doesn't implement `Debug`
+impl<Seal: RgbSeal> Debug for EvaluationReport<Seal> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("EvaluationReport")
+            .field("quarantine", &self.quarantine)
+            .field("pending", &self.pending)
+            .field("commitments", &self.commitments)
+            .finish()
+    }
 }
 
-#[cfg(test)]
-mod test {
-    #![cfg_attr(coverage_nightly, coverage(off))]
+impl<Seal: RgbSeal> Debug for Quarantined<Seal> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Quarantined")
+            .field("opid", &self.opid)
+            .field("reason", &self.reason)
+            .finish_non_exhaustive()
+    }
+}
 
-    use std::collections::HashMap;
-    use std::convert::Infallible;
-    use std::vec;
+/// Reason for an operation to be quarantined.
+#[derive(Debug, Display)]
+#[display(doc_comments)]
+pub enum QuarantineReason<Seal: RgbSeal> {
+    /// {0}
+    Invalid(VerificationError<Seal>),
 
-    use bp::seals::{TxoSeal, TxoSealExt, WOutpoint, WTxoSeal};
-    use bp::{Outpoint, Sats, ScriptPubkey, SeqNo, Tx, TxIn, TxOut, Vout};
-    use strict_encoding::StrictDumb;
-    use ultrasonic::aluvm::alu::{aluasm, CoreConfig, Lib, LibId, LibSite};
-    use ultrasonic::aluvm::FIELD_ORDER_SECP;
-    use ultrasonic::{fe256, CodexId, Genesis, Identity, Input, StateCell, StateData, StateValue};
+    /// the operation descends from the quarantined operation {0}.
+    Ancestor(Opid),
+}
 
-    use super::*;
+/// State of the contract evaluation, accumulated while reading a consignment stream.
+struct Evaluation<'r, Seal: RgbSeal> {
+    contract_id: ContractId,
+    /// Resolver for the witness status, if the operations have to be classified into the state
+    /// layers.
+    resolver: Option<&'r dyn WitnessResolver<Seal>>,
+    /// Seals which are defined by the processed operations and were not closed yet.
+    seals: BTreeMap<CellAddr, Seal>,
+    /// Reverse index of [`Self::seals`], used to detect seal reuse.
+    open: BTreeMap<Seal, CellAddr>,
+    /// Auth tokens of all seal definitions revealed by the processed operations, with the address
+    /// of the first cell defining each of them; used to detect auth token collisions.
+    tokens: BTreeMap<AuthToken, (CellAddr, Seal::Definition)>,
+    /// Addresses of the cells whose seals were closed by the processed operations.
+    spent: BTreeSet<CellAddr>,
+    processed: BTreeMap<Opid, ProcessedOp<Seal>>,
+    /// Memory used for the verification in the paranoid mode (see [`VerifyConfig::paranoid`]).
+    overlay: Option<MemoryOverlay>,
+    /// Known operations which failed the verification in the paranoid mode.
+    known_invalid: BTreeMap<Opid, CallError>,
+    /// Seals which must be defined by the processed operations (see [`VerifyConfig::terminals`]).
+    terminals: BTreeSet<AuthToken>,
+    /// Whether to reject operations which are not ancestors of the terminals.
+    ancestors_only: bool,
+    /// Whether to quarantine invalid operations instead of failing (see
+    /// [`VerifyConfig::best_effort`]).
+    best_effort: bool,
+    /// Operations quarantined in the best-effort mode, in the order of the stream.
+    quarantine: Vec<Quarantined<Seal>>,
+    /// Index of the quarantined operations in [`Self::quarantine`].
+    quarantined: BTreeMap<Opid, usize>,
+    /// Whether to accept operations without a witness (see [`VerifyConfig::pending`]).
+    allow_pending: bool,
+    /// Operations accepted without a witness.
+    pending: BTreeSet<Opid>,
+    /// Commitments of the verified operation witnesses.
+    commitments: BTreeMap<Opid, WitnessCommitment>,
+}
 
-    #[derive(Clone)]
-    struct TestReader(vec::IntoIter<OperationSeals<TxoSeal>>);
-    impl ReadOperation for TestReader {
-        type Seal = TxoSeal;
-        fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
-            Result::<_, Infallible>::Ok(self.0.next())
+impl<'r, Seal: RgbSeal> Evaluation<'r, Seal> {
+    fn new(contract_id: ContractId, config: &VerifyConfig, resolver: Option<&'r dyn WitnessResolver<Seal>>) -> Self {
+        Self {
+            contract_id,
+            resolver,
+            seals: none!(),
+            open: none!(),
+            tokens: none!(),
+            spent: none!(),
+            processed: none!(),
+            overlay: config.paranoid.then(MemoryOverlay::default),
+            known_invalid: none!(),
+            terminals: config.terminals.clone(),
+            ancestors_only: config.ancestors_only,
+            best_effort: config.best_effort,
+            quarantine: none!(),
+            quarantined: none!(),
+            allow_pending: config.pending,
+            pending: none!(),
+            commitments: none!(),
         }
     }
-    impl TestReader {
-        pub fn new(vec: Vec<OperationSeals<TxoSeal>>) -> Self { Self(vec.into_iter()) }
-    }
 
-    struct TestContract {
-        pub codex: Codex,
-        pub contract_id: ContractId,
-        pub libs: HashMap<LibId, Lib>,
-        pub global: HashMap<CellAddr, StateValue>,
-        pub owned: HashMap<CellAddr, StateCell>,
-        pub known_ops: BTreeMap<Opid, Operation>,
-        pub seal_definitions: BTreeMap<Opid, HashMap<u16, WTxoSeal>>,
-        pub witnesses: BTreeMap<Opid, Vec<SealWitness<TxoSeal>>>,
-    }
-    impl Memory for TestContract {
-        fn destructible(&self, addr: CellAddr) -> Option<StateCell> { self.owned.get(&addr).cloned() }
-        fn immutable(&self, addr: CellAddr) -> Option<StateValue> { self.global.get(&addr).cloned() }
-    }
-    impl LibRepo for TestContract {
-        fn get_lib(&self, lib_id: LibId) -> Option<&Lib> { self.libs.get(&lib_id) }
-    }
-    impl ContractApi<TxoSeal> for TestContract {
-        fn contract_id(&self) -> ContractId { self.contract_id }
-        fn codex(&self) -> &Codex { &self.codex }
-        fn repo(&self) -> &impl LibRepo { self }
-        fn memory(&self) -> &impl Memory { self }
-        fn is_known(&self, opid: Opid) -> bool { self.known_ops.contains_key(&opid) }
-        fn apply_operation(&mut self, op: VerifiedOperation) {
-            let opid = op.opid();
-            let op = op.into_operation();
-            for (no, inp) in op.immutable_out.iter().enumerate() {
-                self.global
-                    .insert(CellAddr::new(opid, no as u16), inp.value);
+    /// Completes the evaluation once the whole stream is processed.
+    fn complete(self) -> Result<EvaluationReport<Seal>, VerificationError<Seal>> {
+        if !self.known_invalid.is_empty() {
+            return Err(VerificationError::KnownInvalid(self.known_invalid));
+        }
+        let report = EvaluationReport {
+            quarantine: self.quarantine,
+            pending: self.pending,
+            commitments: self.commitments,
+        };
+        if self.terminals.is_empty() {
+            return Ok(report);
+        }
+
+        // Terminals must be defined by the cells which were not spent by the stream operations.
+        let mut missing = self.terminals;
+        let mut queue = Vec::new();
+        for (opid, op) in &self.processed {
+            let mut defines = false;
+            for (pos, auth) in op.auth.iter().enumerate() {
+                if !self.spent.contains(&CellAddr::new(*opid, pos as u16)) && missing.remove(auth) {
+                    defines = true;
+                }
             }
-            for (no, inp) in op.destructible_out.iter().enumerate() {
-                self.owned.insert(CellAddr::new(opid, no as u16), *inp);
+            if defines {
+                queue.push(*opid);
             }
-            self.known_ops.insert(opid, op);
         }
-        fn apply_seals(&mut self, opid: Opid, seals: SmallOrdMap<u16, WTxoSeal>) {
-            self.seal_definitions.entry(opid).or_default().extend(seals);
+        if !missing.is_empty() {
+            return Err(VerificationError::TerminalsMissing(missing));
         }
-        fn apply_witness(&mut self, opid: Opid, witness: SealWitness<TxoSeal>) {
-            self.witnesses.entry(opid).or_default().push(witness);
+
+        if self.ancestors_only {
+            let mut ancestors = BTreeSet::new();
+            while let Some(opid) = queue.pop() {
+                if !ancestors.insert(opid) {
+                    continue;
+                }
+                if let Some(op) = self.processed.get(&opid) {
+                    queue.extend(op.parents.iter().copied());
+                }
+            }
+            let unrelated = self
+                .processed
+                .iter()
+                .filter(|(opid, op)| !op.is_genesis && !ancestors.contains(*opid))
+                .map(|(opid, _)| *opid)
+                .collect::<BTreeSet<_>>();
+            if !unrelated.is_empty() {
+                return Err(VerificationError::Unrelated(unrelated));
+            }
         }
+        Ok(report)
     }
 
-    fn lib() -> Lib {
-        let code = aluasm! {
-            stop;
-        };
-        Lib::assemble(&code).unwrap()
-    }
+    /// Processes a full operation read from the stream.
+    fn operation<C: ContractApi<Seal> + ?Sized>(
+        &mut self,
+        contract: &mut C,
+        opid: Opid,
+        block: OperationSeals<Seal>,
+        is_genesis: bool,
+    ) -> Result<(), VerificationError<Seal>> {
+        if !self.best_effort || is_genesis {
+            let checked = self.check(contract, opid, &block, is_genesis)?;
+            self.accept(contract, opid, block, checked, is_genesis);
+            return Ok(());
+        }
 
-    fn codex() -> Codex {
-        let lib_id = lib().lib_id();
-        Codex {
-            name: tiny_s!("TestCodex"),
-            developer: Identity::default(),
-            version: default!(),
-            features: default!(),
-            timestamp: 1732529307,
-            field_order: FIELD_ORDER_SECP,
-            input_config: CoreConfig::default(),
-            verification_config: CoreConfig::default(),
-            verifiers: tiny_bmap! {
-                0 => LibSite::new(lib_id, 0),
+        // In the best-effort mode operations descending from the quarantined ones, or failing the
+        // verification, are quarantined instead of failing the whole evaluation.
+        let ancestor = block
+            .operation
+            .destructible_in
+            .iter()
+            .map(|input| input.addr.opid)
+            .chain(block.operation.immutable_in.iter().map(|addr| addr.opid))
+            .find(|parent| self.quarantined.contains_key(parent));
+        let reason = match ancestor {
+            Some(ancestor) => QuarantineReason::Ancestor(ancestor),
+            None => match self.check(contract, opid, &block, is_genesis) {
+                Ok(checked) => {
+                    self.accept(contract, opid, block, checked, false);
+                    return Ok(());
+                }
+                Err(err) => QuarantineReason::Invalid(err),
             },
-        }
+        };
+        self.quarantined.insert(opid, self.quarantine.len());
+        self.quarantine.push(Quarantined { opid, block, reason });
+        Ok(())
     }
 
-    const SEAL_WOUT: WTxoSeal = WTxoSeal {
-        primary: WOutpoint::Wout(Vout::from_u32(0)),
-        secondary: TxoSealExt::Fallback(Outpoint::coinbase()),
-    };
+    /// Whether the state layers of the operations are reported to the contract: either the witness
+    /// statuses are resolved, or operations may be accepted without a witness.
+    fn is_layered(&self) -> bool { self.resolver.is_some() || self.allow_pending }
 
-    const SEAL_1: WTxoSeal = WTxoSeal {
-        primary: WOutpoint::Extern(Outpoint::coinbase()),
-        secondary: TxoSealExt::Fallback(Outpoint::coinbase()),
-    };
+    /// Records the commitment made by a verified operation witness, replacing the commitment of a
+    /// previous witness.
+    fn record_commitment(&mut self, opid: Opid, witness: &SealWitness<Seal>) {
+        match Seal::commitment(witness) {
+            Some(commitment) => self.commitments.insert(opid, commitment),
+            None => self.commitments.remove(&opid),
+        };
+    }
 
-    fn genesis() -> Genesis {
-        let mut genesis = Genesis::strict_dumb();
-        genesis.codex_id = codex().codex_id();
+    /// Verifies that the seals `closed` by an operation are closed by the `witness` over the
+    /// operation id.
+    ///
+    /// Each seal must be closed either through its primary definition, or - if the witness doesn't
+    /// close it and proves that it can't be closed anymore - through its fallback seal (see
+    /// [`RgbSeal::fallback`] and [`RgbSeal::proves_fallback`]). The witness commitment is verified
+    /// once for all the seals, whichever path was used to close them.
+    fn verify_closing(opid: Opid, closed: &[Seal], witness: &SealWitness<Seal>) -> Result<(), VerificationError<Seal>> {
+        let msg: Seal::Message = opid.to_byte_array().into();
+        let pub_id = witness.published.pub_id();
+        let mut seals = Vec::with_capacity(closed.len());
+        let mut fallback = false;
+        for seal in closed {
+            if seal.is_included(msg, witness) {
+                seals.push(seal.clone());
+                continue;
+            }
+            match seal.fallback() {
+                Some(secondary) if secondary.is_included(msg, witness) => {
+                    if !Seal::proves_fallback(witness) {
+                        return Err(VerificationError::FallbackUnproven(pub_id, opid, seal.clone()));
+                    }
+                    fallback = true;
+                    seals.push(secondary);
+                }
+                Some(_) => return Err(VerificationError::FallbackNotClosed(pub_id, opid, seal.clone())),
+                // Let the seal closing procedure report the seal which is not included
+                None => seals.push(seal.clone()),
+            }
+        }
+        witness.verify_seals_closing(&seals, msg).map_err(|e| {
+            if fallback {
+                VerificationError::FallbackCommitment(pub_id, opid, e)
+            } else {
+                VerificationError::SealsNotClosed(pub_id, opid, e)
+            }
+        })
+    }
+
+    /// Checks that the witness-output-based seals defined by an operation point to the outputs of
+    /// the witness, which can be spent.
+    fn check_closable<'s>(
+        opid: Opid,
+        seals: impl IntoIterator<Item = (&'s u16, &'s Seal::Definition)>,
+        published: &Seal::PubWitness,
+    ) -> Result<(), VerificationError<Seal>>
+    where
+        Seal: 's,
+    {
+        for (pos, seal) in seals {
+            if seal.to_src().is_none() && !seal.is_closable(published) {
+                return Err(VerificationError::UnspendableSeal {
+                    addr: CellAddr::new(opid, *pos),
+                    witness: published.pub_id(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that each seal definition matches the auth token of the operation destructible output
+    /// at the same position.
+    fn check_positions(
+        opid: Opid,
+        auth: &[AuthToken],
+        seals: &SmallOrdMap<u16, Seal::Definition>,
+    ) -> Result<(), VerificationError<Seal>> {
+        let mut reported = BTreeMap::new();
+        let mut defined = BTreeMap::new();
+        let mut sources = BTreeMap::new();
+        for (pos, seal) in seals {
+            let token = seal.auth_token();
+            let actual = auth.get(*pos as usize).copied();
+            if actual == Some(token) {
+                continue;
+            }
+            reported.insert(*pos, token);
+            if let Some(actual) = actual {
+                defined.insert(*pos, actual);
+            }
+            sources.insert(*pos, seal.to_string());
+        }
+        if !reported.is_empty() {
+            return Err(VerificationError::SealsDefinitionMismatch { opid, reported, defined, sources });
+        }
+        Ok(())
+    }
+
+    /// Resolves seal definitions of an operation into seals, if possible.
+    fn resolve_seals<'s>(
+        opid: Opid,
+        seals: impl IntoIterator<Item = (&'s u16, &'s Seal::Definition)>,
+        witness_id: Option<<Seal::PubWitness as PublishedWitness<Seal>>::PubId>,
+    ) -> BTreeMap<CellAddr, Seal>
+    where
+        Seal: 's,
+    {
+        seals
+            .into_iter()
+            .filter_map(|(pos, seal)| {
+                let src = seal
+                    .to_src()
+                    .or_else(|| witness_id.and_then(|id| seal.try_resolve(id)))?;
+                Some((CellAddr::new(opid, *pos), src))
+            })
+            .collect()
+    }
+
+    /// Checks that the seals are neither defined twice by an operation, nor are the same as seals
+    /// which are defined by other cells and are not closed yet: closing one of such seals would
+    /// close all the others.
+    fn check_reuse(
+        open: &BTreeMap<Seal, CellAddr>,
+        sources: &BTreeMap<CellAddr, Seal>,
+    ) -> Result<(), VerificationError<Seal>> {
+        let mut defined = BTreeMap::<&Seal, CellAddr>::new();
+        for (addr, seal) in sources {
+            let first = open
+                .get(seal)
+                .copied()
+                .filter(|first| first != addr)
+                .or_else(|| defined.get(seal).copied());
+            if let Some(first) = first {
+                return Err(VerificationError::SealReuse { first, second: *addr });
+            }
+            defined.insert(seal, *addr);
+        }
+        Ok(())
+    }
+
+    /// Checks that the seal definitions of an operation do not produce the same auth token as some
+    /// different seal definition of another cell.
+    ///
+    /// Auth tokens are truncated seal definition commitments (see
+    /// [`crate::AuthTokenVersion::V0`]); while finding a collision is computationally infeasible, a
+    /// colliding definition would allow assigning a cell to a seal different from the one
+    /// committed to by the operation.
+    ///
+    /// The definitions are compared with the ones revealed earlier during the same evaluation and
+    /// with the ones known to the contract (see [`ContractApi::seal_definition`]).
+    fn check_tokens<C: ContractApi<Seal> + ?Sized>(
+        contract: &C,
+        tokens: &BTreeMap<AuthToken, (CellAddr, Seal::Definition)>,
+        opid: Opid,
+        seals: &SmallOrdMap<u16, Seal::Definition>,
+    ) -> Result<(), VerificationError<Seal>> {
+        let mut defined = BTreeMap::<AuthToken, (CellAddr, &Seal::Definition)>::new();
+        for (pos, seal) in seals {
+            let token = seal.auth_token();
+            let second = CellAddr::new(opid, *pos);
+            let stored = contract.seal_definition(token);
+            let known = tokens
+                .get(&token)
+                .map(|(addr, def)| (*addr, def))
+                .into_iter()
+                .chain(stored.as_ref().map(|(addr, def)| (*addr, def)))
+                .chain(defined.get(&token).copied());
+            for (first, def) in known {
+                if def != seal {
+                    return Err(VerificationError::AuthTokenCollision { token, first, second });
+                }
+            }
+            defined.insert(token, (second, seal));
+        }
+        Ok(())
+    }
+
+    /// Records auth tokens of the seal definitions revealed for an operation.
+    fn record_tokens(&mut self, opid: Opid, seals: &SmallOrdMap<u16, Seal::Definition>) {
+        for (pos, seal) in seals {
+            self.tokens
+                .entry(seal.auth_token())
+                .or_insert_with(|| (CellAddr::new(opid, *pos), seal.clone()));
+        }
+    }
+
+    /// Adds a seal for a cell, replacing the seal which was previously resolved for it.
+    fn open_seal(&mut self, addr: CellAddr, seal: Seal) {
+        if let Some(prev) = self.seals.insert(addr, seal.clone()) {
+            self.open.remove(&prev);
+        }
+        self.open.insert(seal, addr);
+    }
+
+    /// Checks an operation without changing the evaluation state (except recording known invalid
+    /// operations in the paranoid mode).
+    fn check<C: ContractApi<Seal> + ?Sized>(
+        &mut self,
+        contract: &C,
+        opid: Opid,
+        block: &OperationSeals<Seal>,
+        is_genesis: bool,
+    ) -> Result<CheckedOp<Seal>, VerificationError<Seal>> {
+        // We need to check that all seal definitions strictly match operation-defined destructible cells
+        let auth = block
+            .operation
+            .destructible_out
+            .iter()
+            .map(|cell| cell.auth)
+            .collect::<Vec<_>>();
+        // Some seals might be unknown to us: we know their commitment auth token but do not know the
+        // definition.
+        Self::check_positions(opid, &auth, &block.defined_seals)?;
+        Self::check_tokens(contract, &self.tokens, opid, &block.defined_seals)?;
+
+        // Collect single-use seal closings by the operation
+        let mut closed = Vec::<Seal>::new();
+        let mut inputs = BTreeSet::new();
+        for input in &block.operation.destructible_in {
+            // The VM doesn't deduplicate inputs, thus a repeated input would be counted twice.
+            if !inputs.insert(input.addr) {
+                return Err(VerificationError::DuplicateInput(opid, input.addr));
+            }
+            let seal = self
+                .seals
+                .get(&input.addr)
+                .ok_or(VerificationError::SealUnknown(input.addr))?;
+            closed.push(seal.clone());
+        }
+
+        if let Some(witness) = &block.witness {
+            Self::verify_closing(opid, &closed, witness)?;
+            Self::check_closable(opid, block.defined_seals.iter(), &witness.published)?;
+        } else if !closed.is_empty() && !self.allow_pending {
+            return Err(VerificationError::NoWitness(opid));
+        }
+
+        let witness_id = block
+            .witness
+            .as_ref()
+            .map(|witness| witness.published.pub_id());
+        let sources = Self::resolve_seals(opid, &block.defined_seals, witness_id);
+        Self::check_reuse(&self.open, &sources)?;
+
+        // If the operation was validated before, we need to skip its validation, since its inputs are not a
+        // part of the state anymore.
+        let verified = match &self.overlay {
+            None if contract.is_known(opid) => None,
+            None => {
+                // Verify the operation
+                let verified = contract.codex().verify(
+                    self.contract_id,
+                    block.operation.clone(),
+                    contract.memory(),
+                    contract.repo(),
+                )?;
+                Some(verified)
+            }
+            // In the paranoid mode we re-verify everything except genesis (which is verified only if
+            // `VerifyConfig::genesis_call` is set) against the memory which we construct ourselves.
+            Some(_) if is_genesis => None,
+            Some(overlay) => {
+                let known = contract.is_known(opid);
+                match contract
+                    .codex()
+                    .verify(self.contract_id, block.operation.clone(), overlay, contract.repo())
+                {
+                    Ok(_) if known => None,
+                    Ok(verified) => Some(verified),
+                    Err(err) if known => {
+                        self.known_invalid.insert(opid, err);
+                        None
+                    }
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        };
+
+        Ok(CheckedOp { auth, closed, sources, verified })
+    }
+
+    /// Accepts a checked operation, updating the evaluation state and the contract.
+    fn accept<C: ContractApi<Seal> + ?Sized>(
+        &mut self,
+        contract: &mut C,
+        opid: Opid,
+        block: OperationSeals<Seal>,
+        checked: CheckedOp<Seal>,
+        is_genesis: bool,
+    ) {
+        let parents = block
+            .operation
+            .destructible_in
+            .iter()
+            .map(|input| input.addr.opid)
+            .chain(block.operation.immutable_in.iter().map(|addr| addr.opid))
+            .collect();
+        for input in &block.operation.destructible_in {
+            if let Some(seal) = self.seals.remove(&input.addr) {
+                self.open.remove(&seal);
+            }
+            self.spent.insert(input.addr);
+        }
+        if let Some(overlay) = &mut self.overlay {
+            overlay.insert(opid, &block.operation);
+        }
+
+        let wout_seals = block
+            .defined_seals
+            .iter()
+            .filter(|(_, seal)| seal.to_src().is_none())
+            .map(|(pos, seal)| (*pos, seal.clone()))
+            .collect::<BTreeMap<_, _>>();
+
+        let mut witness_id = None;
+        let mut published = None;
+        let mut own_status = WitnessStatus::Confirmed;
+        if let Some(witness) = block.witness {
+            let pub_id = witness.published.pub_id();
+            witness_id = Some(pub_id);
+            published = Some(witness.published.clone());
+            if let Some(resolver) = self.resolver {
+                own_status = resolver.witness_status(pub_id);
+            }
+            self.record_commitment(opid, &witness);
+
+            contract.apply_witness(opid, witness);
+        } else if !checked.closed.is_empty() {
+            self.pending.insert(opid);
+            own_status = WitnessStatus::Tentative;
+        }
+        let status = self.status(own_status, &parents);
+
+        // Now we can add operation-defined seals to the set of known seals. Each witness actually
+        // produces its own set of witness-output-based seal sources.
+        for (addr, seal) in checked.sources {
+            self.open_seal(addr, seal);
+        }
+        for parent in &parents {
+            if let Some(parent) = self.processed.get_mut(parent) {
+                parent.children.insert(opid);
+            }
+        }
+        self.processed.insert(opid, ProcessedOp {
+            is_genesis,
+            parents,
+            children: none!(),
+            auth: checked.auth,
+            closed: checked.closed,
+            wout_seals,
+            witness_id,
+            published,
+            own_status,
+            status,
+        });
+        if let Some(operation) = checked.verified.filter(|_| !is_genesis) {
+            contract.apply_operation(operation);
+        }
+        if self.is_layered() && !is_genesis {
+            contract.apply_status(opid, status);
+        }
+
+        if !block.defined_seals.is_empty() {
+            self.record_tokens(opid, &block.defined_seals);
+            contract.apply_seals(opid, block.defined_seals);
+        }
+    }
+
+    /// Processes a witness update for an already processed operation.
+    fn witness<C: ContractApi<Seal> + ?Sized>(
+        &mut self,
+        contract: &mut C,
+        opid: Opid,
+        witness: SealWitness<Seal>,
+    ) -> Result<(), VerificationError<Seal>> {
+        if let Some(no) = self.quarantined.get(&opid) {
+            self.quarantine[*no].block.witness = Some(witness);
+            return Ok(());
+        }
+        let op = self
+            .processed
+            .get_mut(&opid)
+            .ok_or(VerificationError::UnknownOperation(opid))?;
+        Self::verify_closing(opid, &op.closed, &witness)?;
+        // Descendants which have already spent witness-output-based seals of the operation have
+        // closed the outputs of the previous witness, thus it can't be replaced anymore.
+        let pub_id = witness.published.pub_id();
+        if op.witness_id.is_some_and(|id| id != pub_id) {
+            let spent = op
+                .wout_seals
+                .keys()
+                .map(|pos| CellAddr::new(opid, *pos))
+                .find(|addr| self.spent.contains(addr));
+            if let Some(spent) = spent {
+                return Err(VerificationError::WitnessReplacement { opid, spent });
+            }
+        }
+        let unspent = op
+            .wout_seals
+            .iter()
+            .filter(|(pos, _)| !self.spent.contains(&CellAddr::new(opid, **pos)));
+        Self::check_closable(opid, unspent, &witness.published)?;
+
+        // The new witness replaces witness-output-based seals which were not spent yet.
+        let mut sources = Self::resolve_seals(opid, &op.wout_seals, Some(pub_id));
+        sources.retain(|addr, _| !self.spent.contains(addr));
+        Self::check_reuse(&self.open, &sources)?;
+        op.witness_id = Some(pub_id);
+        op.published = Some(witness.published.clone());
+        op.own_status = match self.resolver {
+            Some(resolver) => resolver.witness_status(pub_id),
+            None => WitnessStatus::Confirmed,
+        };
+        self.pending.remove(&opid);
+        for (addr, seal) in sources {
+            self.open_seal(addr, seal);
+        }
+        self.record_commitment(opid, &witness);
+
+        contract.apply_witness(opid, witness);
+        if self.is_layered() {
+            self.restatus(contract, opid);
+        }
+        Ok(())
+    }
+
+    /// Computes state layer of an operation from the status of its own witness and the layers of
+    /// its ancestors.
+    fn status(&self, own_status: WitnessStatus, parents: &BTreeSet<Opid>) -> WitnessStatus {
+        // Ancestors which are not a part of the stream are known to the contract, and we have no
+        // information about their witnesses, thus we consider them confirmed.
+        parents
+            .iter()
+            .filter_map(|parent| self.processed.get(parent))
+            .map(|parent| parent.status)
+            .fold(own_status, WitnessStatus::min)
+    }
+
+    /// Re-computes state layer of an operation and its descendants, reporting the changes to the
+    /// contract.
+    fn restatus<C: ContractApi<Seal> + ?Sized>(&mut self, contract: &mut C, opid: Opid) {
+        let mut queue = vec![opid];
+        while let Some(opid) = queue.pop() {
+            let Some(op) = self.processed.get(&opid) else { continue };
+            let status = self.status(op.own_status, &op.parents);
+            if status == op.status {
+                continue;
+            }
+            let is_genesis = op.is_genesis;
+            if let Some(op) = self.processed.get_mut(&opid) {
+                op.status = status;
+                queue.extend(op.children.iter().copied());
+            }
+            if !is_genesis {
+                contract.apply_status(opid, status);
+            }
+        }
+    }
+
+    /// Polls the resolver once more for the witnesses which were tentative when their operations
+    /// were processed, promoting the operations whose witnesses got confirmed since then.
+    fn repoll<C: ContractApi<Seal> + ?Sized>(&mut self, contract: &mut C) {
+        let Some(resolver) = self.resolver else { return };
+        let promoted = self
+            .processed
+            .iter_mut()
+            .filter(|(_, op)| op.own_status == WitnessStatus::Tentative)
+            .filter_map(|(opid, op)| {
+                let witness_id = op.witness_id?;
+                op.own_status = resolver.witness_status(witness_id);
+                (op.own_status == WitnessStatus::Confirmed).then_some(*opid)
+            })
+            .collect::<Vec<_>>();
+        for opid in promoted {
+            self.restatus(contract, opid);
+        }
+    }
+
+    /// Processes a late reveal of seal definitions for an already processed operation.
+    fn seals<C: ContractApi<Seal> + ?Sized>(
+        &mut self,
+        contract: &mut C,
+        opid: Opid,
+        revealed: SmallOrdMap<u16, Seal::Definition>,
+    ) -> Result<(), VerificationError<Seal>> {
+        if let Some(no) = self.quarantined.get(&opid) {
+            let defined_seals = &mut self.quarantine[*no].block.defined_seals;
+            for (pos, seal) in revealed {
+                // The map is keyed by `u16`, thus it can't exceed its confinement.
+                let _ = defined_seals.insert(pos, seal);
+            }
+            return Ok(());
+        }
+        let op = self
+            .processed
+            .get_mut(&opid)
+            .ok_or(VerificationError::UnknownOperation(opid))?;
+        Self::check_positions(opid, &op.auth, &revealed)?;
+        Self::check_tokens(&*contract, &self.tokens, opid, &revealed)?;
+        if let Some(published) = &op.published {
+            Self::check_closable(opid, revealed.iter(), published)?;
+        }
+
+        let mut sources = Self::resolve_seals(opid, &revealed, op.witness_id);
+        sources.retain(|addr, _| !self.spent.contains(addr));
+        Self::check_reuse(&self.open, &sources)?;
+        for (pos, seal) in &revealed {
+            if seal.to_src().is_none() {
+                op.wout_seals.insert(*pos, seal.clone());
+            }
+        }
+        for (addr, seal) in sources {
+            self.open_seal(addr, seal);
+        }
+
+        self.record_tokens(opid, &revealed);
+        contract.apply_seals(opid, revealed);
+        Ok(())
+    }
+}
+
+/// Contract memory constructed from the operations read from the consignment stream.
+#[derive(Default)]
+struct MemoryOverlay {
+    destructible: BTreeMap<CellAddr, StateCell>,
+    immutable: BTreeMap<CellAddr, StateValue>,
+}
+
+impl MemoryOverlay {
+    fn insert(&mut self, opid: Opid, op: &Operation) {
+        for (no, cell) in op.destructible_out.iter().enumerate() {
+            self.destructible
+                .insert(CellAddr::new(opid, no as u16), *cell);
+        }
+        for (no, data) in op.immutable_out.iter().enumerate() {
+            self.immutable
+                .insert(CellAddr::new(opid, no as u16), data.value);
+        }
+    }
+}
+
+impl Memory for MemoryOverlay {
+    fn destructible(&self, addr: CellAddr) -> Option<StateCell> { self.destructible.get(&addr).copied() }
+    fn immutable(&self, addr: CellAddr) -> Option<StateValue> { self.immutable.get(&addr).copied() }
+}
+
+/// Results of the checks of an operation which is not accepted yet.
+struct CheckedOp<Seal: RgbSeal> {
+    auth: Vec<AuthToken>,
+    closed: Vec<Seal>,
+    sources: BTreeMap<CellAddr, Seal>,
+    verified: Option<VerifiedOperation>,
+}
+
+/// Information about an operation read from the consignment stream, which is required to process
+/// subsequent witness updates and seal reveals for it.
+struct ProcessedOp<Seal: RgbSeal> {
+    is_genesis: bool,
+    /// Operations whose cells are read or spent by the operation.
+    parents: BTreeSet<Opid>,
+    /// Processed operations reading or spending cells of the operation.
+    children: BTreeSet<Opid>,
+    /// Auth tokens of the operation destructible outputs, in their order.
+    auth: Vec<AuthToken>,
+    /// Seals closed by the operation.
+    closed: Vec<Seal>,
+    /// Known seal definitions which are relative to the operation witness.
+    wout_seals: BTreeMap<u16, Seal::Definition>,
+    /// Id of the most recent valid witness for the operation.
+    witness_id: Option<<Seal::PubWitness as PublishedWitness<Seal>>::PubId>,
+    /// The most recent valid witness for the operation, against which the witness-output-based
+    /// seals revealed later are checked.
+    published: Option<Seal::PubWitness>,
+    /// Status of the operation own witness.
+    own_status: WitnessStatus,
+    /// State layer of the operation, accounting for the status of its ancestors.
+    status: WitnessStatus,
+}
+
+impl<Seal: RgbSeal, C: ContractApi<Seal>> ContractVerify<Seal> for C {}
+
+/// Errors returned from the verification.
+#[derive(Display, Error, From)]
+#[display(doc_comments)]
+pub enum VerificationError<Seal: RgbSeal> {
+    /// error reading the consignment stream.
+    ///
+    /// Details: {0}
+    Stream(Box<dyn Error>),
+
+    /// genesis does not commit to the codex id; a wrong contract genesis is used.
+    NoCodexCommitment,
+
+    /// contract id {expected} does not match the id {found} derived from the contract genesis.
+    #[allow(missing_docs)]
+    ContractIdMismatch { expected: ContractId, found: ContractId },
+
+    /// genesis uses call {found}, while it is required to use call {expected}.
+    #[allow(missing_docs)]
+    GenesisCall { expected: CallId, found: CallId },
+
+    /// genesis doesn't pass the codex verification.
+    ///
+    /// Details: {0}
+    GenesisInvalid(CallError),
+
+    /// no witness known for the operation {0}.
+    NoWitness(Opid),
+
+    /// single-use seals are not closed properly with witness {0} for operation {1}.
+    ///
+    /// Details: {2}
+    SealsNotClosed(<Seal::PubWitness as PublishedWitness<Seal>>::PubId, Opid, SealError<Seal>),
+
+    /// seal {2} is closed neither through its primary definition nor through its fallback seal
+    /// with witness {0} for operation {1}.
+    FallbackNotClosed(<Seal::PubWitness as PublishedWitness<Seal>>::PubId, Opid, Seal),
+
+    /// seal {2} is closed through its fallback seal with witness {0} for operation {1}, but the
+    /// witness doesn't prove that the primary seal definition can't be closed anymore.
+    FallbackUnproven(<Seal::PubWitness as PublishedWitness<Seal>>::PubId, Opid, Seal),
+
+    /// some of single-use seals are closed through their fallback seals with witness {0} for
+    /// operation {1}, but the witness commitment is invalid.
+    ///
+    /// Details: {2}
+    FallbackCommitment(<Seal::PubWitness as PublishedWitness<Seal>>::PubId, Opid, SealError<Seal>),
+
+    /// unknown seal definition for cell address {0}.
+    SealUnknown(CellAddr),
+
+    /// operation {0} spends cell {1} more than once.
+    DuplicateInput(Opid, CellAddr),
+
+    /// seal for cell address {addr} is defined by an output of the witness {witness}, which
+    /// doesn't exist or can't be spent.
+    #[allow(missing_docs)]
+    UnspendableSeal {
+        addr: CellAddr,
+        witness: <Seal::PubWitness as PublishedWitness<Seal>>::PubId,
+    },
+
+    /// seal defined for cell address {second} is the same as the seal for cell address {first},
+    /// which is not closed yet.
+    #[allow(missing_docs)]
+    SealReuse { first: CellAddr, second: CellAddr },
+
+    /// seal definition for cell address {second} differs from the seal definition for cell address
+    /// {first}, but produces the same auth token {token}.
+    #[allow(missing_docs)]
+    AuthTokenCollision {
+        token: AuthToken,
+        first: CellAddr,
+        second: CellAddr,
+    },
+
+    /// witness of operation {opid} can't be replaced, since the seal at {spent} defined by its
+    /// output was already spent.
+    #[allow(missing_docs)]
+    WitnessReplacement { opid: Opid, spent: CellAddr },
+
+    /// operation {0} claims to be a contract genesis, which must be present only once at the start
+    /// of the consignment stream.
+    RepeatedGenesis(Opid),
+
+    /// stream item references operation {0}, which was not read from the consignment stream
+    /// before.
+    UnknownOperation(Opid),
+
+    /// seals, reported to be defined by the operation {opid}, do not match the assignments at the
+    /// same positions in the operation.
+    ///
+    /// Actual operation seals from the assignments at the mismatching positions: {defined:#?}
+    ///
+    /// Reported seals at the mismatching positions: {reported:#?}
+    ///
+    /// Sources for the mismatching reported seals: {sources:#?}
+    #[allow(missing_docs)]
+    SealsDefinitionMismatch {
+        opid: Opid,
+        reported: BTreeMap<u16, AuthToken>,
+        defined: BTreeMap<u16, AuthToken>,
+        sources: BTreeMap<u16, String>,
+    },
+
+    /// known operations do not pass the verification: {0:#?}
+    KnownInvalid(BTreeMap<Opid, CallError>),
+
+    /// seals {0:#?} are not defined by the unspent outputs of the operations from the consignment.
+    TerminalsMissing(BTreeSet<AuthToken>),
+
+    /// operations {0:#?} are not ancestors of the operations defining the expected seals.
+    ///
+    /// The operations were already applied to the contract, which state must be discarded (see
+    /// [`VerifyConfig::ancestors_only`]).
+    Unrelated(BTreeSet<Opid>),
+
+    /// Eror returned by the virtual machine script.
+    #[from]
+    #[display(inner)]
+    Vm(CallError),
+}
+
+// We need manual implementation since otherwise we get an unneeded `Seal::PubWitness: Debug` bound
+impl<Seal: RgbSeal> Debug for VerificationError<Seal> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result { write!(f, "{}", self) }
+}
+
+#[cfg(all(test, feature = "bitcoin"))]
+pub(crate) mod test {
+    #![cfg_attr(coverage_nightly, coverage(off))]
+
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::vec;
+
+    use amplify::confinement::Confined;
+    use amplify::num::u5;
+    use bp::seals::mpc::{MessageMap, MessageSource};
+    use bp::seals::{mmb, mpc, Anchor, TxoSeal, TxoSealExt, WOutpoint, WTxoSeal};
+    use bp::{Outpoint, Sats, ScriptPubkey, SeqNo, Tx, TxIn, TxOut, Txid, Vout};
+    use commit_verify::CommitId;
+    use strict_encoding::StrictDumb;
+    use ultrasonic::aluvm::alu::{aluasm, CoreConfig, Lib, LibId, LibSite};
+    use ultrasonic::aluvm::FIELD_ORDER_SECP;
+    use ultrasonic::{fe256, CodexId, Genesis, Identity, Input, StateCell, StateData, StateValue};
+
+    use super::*;
+    use crate::mock::{MockSeal, MockSealDef, MockWitness};
+    use crate::multi::{MultiSeal, MultiSealDef, MultiWitnessId};
+    use crate::stream::ReadOperationExt;
+    use crate::CommitmentMethod;
+
+    pub(crate) struct TestReader<Seal: RgbSeal = TxoSeal>(vec::IntoIter<OperationSeals<Seal>>);
+    impl<Seal: RgbSeal> Clone for TestReader<Seal>
+    where OperationSeals<Seal>: Clone
+    {
+        fn clone(&self) -> Self { Self(self.0.clone()) }
+    }
+    impl<Seal: RgbSeal> ReadOperation for TestReader<Seal> {
+        type Seal = Seal;
+        fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+            Result::<_, Infallible>::Ok(self.0.next())
+        }
+    }
+    impl<Seal: RgbSeal> TestReader<Seal> {
+        pub fn new(vec: Vec<OperationSeals<Seal>>) -> Self { Self(vec.into_iter()) }
+    }
+
+    struct ItemReader<Seal: RgbSeal = TxoSeal>(vec::IntoIter<StreamItem<Seal>>);
+    impl<Seal: RgbSeal> ReadOperation for ItemReader<Seal> {
+        type Seal = Seal;
+        fn read_operation(&mut self) -> Result<Option<OperationSeals<Self::Seal>>, impl Error + 'static> {
+            Result::<_, Infallible>::Ok(self.0.find_map(|item| match item {
+                StreamItem::Operation(op) => Some(op),
+                _ => None,
+            }))
+        }
+        fn read_item(&mut self) -> Result<Option<StreamItem<Self::Seal>>, impl Error + 'static> {
+            Result::<_, Infallible>::Ok(self.0.next())
+        }
+    }
+    impl<Seal: RgbSeal> ItemReader<Seal> {
+        pub fn new(vec: Vec<StreamItem<Seal>>) -> Self { Self(vec.into_iter()) }
+    }
+
+    pub(crate) struct TestContract<Seal: RgbSeal = TxoSeal> {
+        pub meta: ContractMeta,
+        pub codex: Codex,
+        pub contract_id: ContractId,
+        pub libs: HashMap<LibId, Lib>,
+        pub global: HashMap<CellAddr, StateValue>,
+        pub owned: HashMap<CellAddr, StateCell>,
+        pub known_ops: BTreeMap<Opid, Operation>,
+        pub seal_definitions: BTreeMap<Opid, HashMap<u16, Seal::Definition>>,
+        pub tokens: BTreeMap<AuthToken, (CellAddr, Seal::Definition)>,
+        pub witnesses: BTreeMap<Opid, Vec<SealWitness<Seal>>>,
+        pub statuses: BTreeMap<Opid, WitnessStatus>,
+    }
+    impl<Seal: RgbSeal> Memory for TestContract<Seal> {
+        fn destructible(&self, addr: CellAddr) -> Option<StateCell> { self.owned.get(&addr).cloned() }
+        fn immutable(&self, addr: CellAddr) -> Option<StateValue> { self.global.get(&addr).cloned() }
+    }
+    impl<Seal: RgbSeal> LibRepo for TestContract<Seal> {
+        fn get_lib(&self, lib_id: LibId) -> Option<&Lib> { self.libs.get(&lib_id) }
+    }
+    impl<Seal: RgbSeal> ContractApi<Seal> for TestContract<Seal> {
+        fn contract_id(&self) -> ContractId { self.contract_id }
+        fn meta(&self) -> &ContractMeta { &self.meta }
+        fn codex(&self) -> &Codex { &self.codex }
+        fn repo(&self) -> &impl LibRepo { self }
+        fn memory(&self) -> &impl Memory { self }
+        fn is_known(&self, opid: Opid) -> bool { self.known_ops.contains_key(&opid) }
+        fn apply_operation(&mut self, op: VerifiedOperation) {
+            let opid = op.opid();
+            let op = op.into_operation();
+            for (no, inp) in op.immutable_out.iter().enumerate() {
+                self.global
+                    .insert(CellAddr::new(opid, no as u16), inp.value);
+            }
+            for (no, inp) in op.destructible_out.iter().enumerate() {
+                self.owned.insert(CellAddr::new(opid, no as u16), *inp);
+            }
+            self.known_ops.insert(opid, op);
+        }
+        fn apply_seals(&mut self, opid: Opid, seals: SmallOrdMap<u16, Seal::Definition>) {
+            for (pos, seal) in &seals {
+                self.tokens
+                    .entry(seal.auth_token())
+                    .or_insert((CellAddr::new(opid, *pos), seal.clone()));
+            }
+            self.seal_definitions.entry(opid).or_default().extend(seals);
+        }
+        fn seal_definition(&self, token: AuthToken) -> Option<(CellAddr, Seal::Definition)> {
+            self.tokens.get(&token).cloned()
+        }
+        fn apply_witness(&mut self, opid: Opid, witness: SealWitness<Seal>) {
+            self.witnesses.entry(opid).or_default().push(witness);
+        }
+        fn apply_status(&mut self, opid: Opid, status: WitnessStatus) { self.statuses.insert(opid, status); }
+    }
+
+    fn lib() -> Lib {
+        let code = aluasm! {
+            stop;
+        };
+        Lib::assemble(&code).unwrap()
+    }
+
+    fn codex() -> Codex {
+        let lib_id = lib().lib_id();
+        Codex {
+            name: tiny_s!("TestCodex"),
+            developer: Identity::default(),
+            version: default!(),
+            features: default!(),
+            timestamp: 1732529307,
+            field_order: FIELD_ORDER_SECP,
+            input_config: CoreConfig::default(),
+            verification_config: CoreConfig::default(),
+            verifiers: tiny_bmap! {
+                0 => LibSite::new(lib_id, 0),
+            },
+        }
+    }
+
+    const SEAL_WOUT: WTxoSeal = WTxoSeal {
+        primary: WOutpoint::Wout(Vout::from_u32(0)),
+        secondary: TxoSealExt::Fallback(Outpoint::coinbase()),
+    };
+
+    const SEAL_1: WTxoSeal = WTxoSeal {
+        primary: WOutpoint::Extern(Outpoint::coinbase()),
+        secondary: TxoSealExt::Fallback(Outpoint::coinbase()),
+    };
+
+    pub(crate) fn genesis() -> Genesis {
+        let mut genesis = Genesis::strict_dumb();
+        genesis.codex_id = codex().codex_id();
         genesis.immutable_out = small_vec![StateData::new(0u64, 1000u64)];
         genesis.destructible_out = small_vec![StateCell {
             data: StateValue::None,
@@ -428,297 +1644,1408 @@ mod test {
         genesis
     }
 
-    fn contract() -> TestContract {
-        let lib = lib();
-        let lib_id = lib.lib_id();
+    pub(crate) fn issue() -> Issue { issue_with(genesis()) }
+
+    fn issue_with(genesis: Genesis) -> Issue {
+        Issue {
+            version: default!(),
+            meta: ContractMeta::strict_dumb(),
+            codex: codex(),
+            genesis,
+        }
+    }
+
+    pub(crate) fn contract() -> TestContract { contract_with(genesis()) }
+
+    pub(crate) fn contract_with<Seal: RgbSeal>(genesis: Genesis) -> TestContract<Seal> {
+        let lib = lib();
+        let lib_id = lib.lib_id();
+        let issue = issue_with(genesis);
+        let contract_id = issue.contract_id();
+        let genesis_op = issue.genesis.to_operation(contract_id);
+        let genesis_opid = genesis_op.opid();
+        TestContract {
+            meta: issue.meta,
+            codex: issue.codex,
+            contract_id,
+            libs: map! { lib_id => lib },
+            global: none!(),
+            owned: map! { CellAddr::new(genesis_opid, 0) => genesis_op.destructible_out[0] },
+            known_ops: bmap! { genesis_opid => genesis_op },
+            seal_definitions: bmap! { genesis_opid => none!() },
+            tokens: none!(),
+            witnesses: bmap! { genesis_opid => none!() },
+            statuses: none!(),
+        }
+    }
+
+    pub(crate) fn operation() -> Operation {
+        let genesis = genesis();
+        let contract = contract();
+        let genesis_op = genesis.to_operation(contract.contract_id);
+        let genesis_opid = genesis_op.opid();
+        Operation {
+            version: default!(),
+            contract_id: contract.contract_id,
+            call_id: 0,
+            nonce: fe256::ZERO,
+            witness: StateValue::None,
+            destructible_in: small_vec![Input {
+                addr: CellAddr::new(genesis_opid, 0),
+                witness: StateValue::None
+            }],
+            immutable_in: Default::default(),
+            destructible_out: Default::default(),
+            immutable_out: Default::default(),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn run(reader: TestReader) -> Result<(), VerificationError<TxoSeal>> {
+        let mut contract = contract();
+        contract.evaluate(reader.clone())?;
+
+        // Check contract values
+        let mut ops = bmap! {};
+        let mut seals = bmap! {};
+        let mut witnesses = bmap! {};
+        for entry in reader.0 {
+            let opid = entry.operation.opid();
+            ops.insert(opid, entry.operation);
+            seals.insert(opid, entry.defined_seals.into_iter().collect());
+            witnesses.insert(opid, entry.witness.into_iter().collect());
+        }
+
+        ops.pop_first();
+        let (genesis_opid, genesis_op) = contract.known_ops.first_key_value().unwrap();
+        ops.insert(*genesis_opid, genesis_op.clone());
+        seals.pop_first();
+        let (genesis_opid, definitions) = contract.seal_definitions.first_key_value().unwrap();
+        seals.insert(*genesis_opid, definitions.clone());
+        witnesses.pop_first();
+        let (genesis_opid, definitions) = contract.witnesses.first_key_value().unwrap();
+        witnesses.insert(*genesis_opid, definitions.clone());
+
+        assert_eq!(ops, contract.known_ops);
+        assert_eq!(seals, contract.seal_definitions);
+        assert_eq!(witnesses, contract.witnesses);
+        Ok(())
+    }
+
+    #[test]
+    fn empty() {
+        let reader = TestReader::new(vec![]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    fn genesis_only() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+
+        let reader =
+            TestReader::new(vec![OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None }]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "genesis does not commit to the codex id; a wrong contract genesis is used.")]
+    fn invalid_genesis() {
+        let mut genesis = genesis();
+        genesis.codex_id = CodexId::from_byte_array([0xAD; 32]);
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+
+        let reader =
+            TestReader::new(vec![OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None }]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "seals, reported to be defined by the operation \
+                               X6CWo5JcxGI6S2DYeuHzsTXQDQ1SM5tdKtcJ2DaruJo, do not match the assignments at the same \
+                               positions in the operation.
+Actual operation seals from the assignments at the mismatching positions: {
+    0: AuthToken(
+        fe256(
+            0x0000141b74832b85ca7bc7e2899cc3e5617a29ac4340f09b105524a6f62bd597,
+        ),
+    ),
+}
+Reported seals at the mismatching positions: {
+    0: AuthToken(
+        fe256(
+            0x000046c31ad97975e90e4ab2ee247f0e2f39ec8461823023e977cc14bcda14f5,
+        ),
+    ),
+}
+Sources for the mismatching reported seals: {
+    0: \"~:0/00000000000000000000000000000000000000000000000000000000000000000000000000000000\",
+}")]
+    fn invalid_seals() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+
+        let reader = TestReader::new(vec![OperationSeals {
+            operation: genesis_op,
+            defined_seals: small_bmap! { 0 => WTxoSeal::strict_dumb() },
+            witness: None,
+        }]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown seal definition for cell address X6CWo5JcxGI6S2DYeuHzsTXQDQ1SM5tdKtcJ2DaruJo:0.")]
+    fn seal_unknown() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let operation = operation();
+
+        let reader = TestReader::new(vec![
+            OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None },
+            OperationSeals { operation, defined_seals: none!(), witness: None },
+        ]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown seal definition for cell address KhFdVHszVPHdNk75dFd7ToHseKHLK0lJwwk5Ap7fKy0:0.")]
+    fn genesis_with_wout() {
+        let mut genesis = genesis();
+        genesis.destructible_out[0].auth = SEAL_WOUT.auth_token();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let mut contract: TestContract = contract_with(genesis.clone());
+        let mut operation = operation();
+        operation.contract_id = contract.contract_id;
+        operation.destructible_in[0].addr = CellAddr::new(genesis.opid(contract.contract_id), 0);
+
+        let reader = TestReader::new(vec![
+            OperationSeals {
+                operation: genesis_op,
+                defined_seals: small_bmap! { 0 => SEAL_WOUT},
+                witness: None,
+            },
+            OperationSeals { operation, defined_seals: none!(), witness: None },
+        ]);
+        contract.evaluate(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "no witness known for the operation YlruG8Cs88ltucEncqsemWG1~VJrfAds1cnw9_af9f0.")]
+    fn no_witness() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let operation = operation();
+
+        let reader = TestReader::new(vec![
+            OperationSeals {
+                operation: genesis_op,
+                defined_seals: small_bmap! { 0 => SEAL_1 },
+                witness: None,
+            },
+            OperationSeals { operation, defined_seals: none!(), witness: None },
+        ]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "single-use seals are not closed properly with witness \
+                               4ebd325a4b394cff8c57e8317ccf5a8d0e2bdf1b8526f8aad6c8e43d8240621a for operation \
+                               YlruG8Cs88ltucEncqsemWG1~VJrfAds1cnw9_af9f0.
+Details: seal \
+                               0000000000000000000000000000000000000000000000000000000000000000:0/\
+                               0000000000000000000000000000000000000000000000000000000000000000:0 is not included in \
+                               the public witness 4ebd325a4b394cff8c57e8317ccf5a8d0e2bdf1b8526f8aad6c8e43d8240621a")]
+    fn seals_unclosed() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let operation = operation();
+
+        let reader = TestReader::new(vec![
+            OperationSeals {
+                operation: genesis_op,
+                defined_seals: small_bmap! { 0 => SEAL_1 },
+                witness: None,
+            },
+            OperationSeals {
+                operation,
+                defined_seals: none!(),
+                witness: Some(SealWitness::new(strict_dumb!(), strict_dumb!())),
+            },
+        ]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "ingle-use seals are not closed properly with witness \
+                               1692606e775129a6733b6dc48ec7f5771f8e30d8c5304c0949d36efad2411812 for operation \
+                               YlruG8Cs88ltucEncqsemWG1~VJrfAds1cnw9_af9f0.
+Details: seal \
+                               0000000000000000000000000000000000000000000000000000000000000000:0/\
+                               0000000000000000000000000000000000000000000000000000000000000000:0 is not included in \
+                               the public witness 1692606e775129a6733b6dc48ec7f5771f8e30d8c5304c0949d36efad2411812")]
+    fn not_spending_utxo() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let operation = operation();
+
+        let mut witness = Tx::strict_dumb();
+        witness
+            .outputs
+            .push(TxOut {
+                value: Sats::ZERO,
+                script_pubkey: ScriptPubkey::op_return(&[]),
+            })
+            .unwrap();
+
+        let reader = TestReader::new(vec![
+            OperationSeals {
+                operation: genesis_op,
+                defined_seals: small_bmap! { 0 => SEAL_1 },
+                witness: None,
+            },
+            OperationSeals {
+                operation,
+                defined_seals: none!(),
+                witness: Some(SealWitness::new(witness, strict_dumb!())),
+            },
+        ]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "single-use seals are not closed properly with witness \
+                               0520b790b442e9c023e2ea0e0e284fbe60086d64f01037082f19464b44f9642e for operation \
+                               YlruG8Cs88ltucEncqsemWG1~VJrfAds1cnw9_af9f0.
+Details: seal \
+                               0000000000000000000000000000000000000000000000000000000000000000:0/\
+                               0000000000000000000000000000000000000000000000000000000000000000:0 is not included in \
+                               the public witness 0520b790b442e9c023e2ea0e0e284fbe60086d64f01037082f19464b44f9642e")]
+    fn missing_commitment() {
         let genesis = genesis();
-        let genesis_op = genesis.to_operation(ContractId::strict_dumb());
-        let genesis_opid = genesis_op.opid();
-        TestContract {
-            codex: codex(),
-            contract_id: ContractId::strict_dumb(),
-            libs: map! { lib_id => lib },
-            global: none!(),
-            owned: map! { CellAddr::new(genesis_opid, 0) => genesis_op.destructible_out[0] },
-            known_ops: bmap! { genesis_opid => genesis_op },
-            seal_definitions: bmap! { genesis_opid => none!() },
-            witnesses: bmap! { genesis_opid => none!() },
-        }
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let operation = operation();
+
+        let mut witness = Tx::strict_dumb();
+        witness
+            .inputs
+            .push(TxIn {
+                prev_output: Outpoint::coinbase(),
+                sig_script: none!(),
+                sequence: SeqNo::from_consensus_u32(0),
+                witness: none!(),
+            })
+            .unwrap();
+        witness
+            .outputs
+            .push(TxOut {
+                value: Sats::ZERO,
+                script_pubkey: ScriptPubkey::op_return(&[]),
+            })
+            .unwrap();
+
+        let reader = TestReader::new(vec![
+            OperationSeals {
+                operation: genesis_op,
+                defined_seals: small_bmap! { 0 => SEAL_1 },
+                witness: None,
+            },
+            OperationSeals {
+                operation,
+                defined_seals: none!(),
+                witness: Some(SealWitness::new(witness, strict_dumb!())),
+            },
+        ]);
+        run(reader).unwrap();
     }
 
-    fn operation() -> Operation {
+    #[test]
+    #[should_panic(expected = "no witness known for the operation YlruG8Cs88ltucEncqsemWG1~VJrfAds1cnw9_af9f0.")]
+    fn seals_reveal() {
         let genesis = genesis();
-        let contract = contract();
-        let genesis_op = genesis.to_operation(contract.contract_id);
-        let genesis_opid = genesis_op.opid();
-        Operation {
-            version: default!(),
-            contract_id: contract.contract_id,
-            call_id: 0,
-            nonce: fe256::ZERO,
-            witness: StateValue::None,
-            destructible_in: small_vec![Input {
-                addr: CellAddr::new(genesis_opid, 0),
-                witness: StateValue::None
-            }],
-            immutable_in: Default::default(),
-            destructible_out: Default::default(),
-            immutable_out: Default::default(),
-        }
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let genesis_opid = issue().genesis_opid();
+        let operation = operation();
+
+        let reader = ItemReader::new(vec![
+            OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None }.into(),
+            StreamItem::Seals(genesis_opid, small_bmap! { 0 => SEAL_1 }),
+            OperationSeals { operation, defined_seals: none!(), witness: None }.into(),
+        ]);
+        contract().evaluate(reader).unwrap();
     }
 
-    #[allow(clippy::result_large_err)]
-    fn run(reader: TestReader) -> Result<(), VerificationError<TxoSeal>> {
+    #[test]
+    #[should_panic(expected = "seals, reported to be defined by the operation \
+                               X6CWo5JcxGI6S2DYeuHzsTXQDQ1SM5tdKtcJ2DaruJo, do not match the assignments at the same \
+                               positions in the operation.")]
+    fn seals_reveal_mismatch() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let genesis_opid = issue().genesis_opid();
+
+        let reader = ItemReader::new(vec![
+            OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None }.into(),
+            StreamItem::Seals(genesis_opid, small_bmap! { 0 => SEAL_WOUT }),
+        ]);
+        contract().evaluate(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "stream item references operation YlruG8Cs88ltucEncqsemWG1~VJrfAds1cnw9_af9f0, which \
+                               was not read from the consignment stream before.")]
+    fn witness_unknown_operation() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let opid = operation().opid();
+
+        let reader = ItemReader::new(vec![
+            OperationSeals {
+                operation: genesis_op,
+                defined_seals: small_bmap! { 0 => SEAL_1 },
+                witness: None,
+            }
+            .into(),
+            StreamItem::Witness(opid, SealWitness::new(strict_dumb!(), strict_dumb!())),
+        ]);
+        contract().evaluate(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "single-use seals are not closed properly with witness \
+                               4ebd325a4b394cff8c57e8317ccf5a8d0e2bdf1b8526f8aad6c8e43d8240621a for operation \
+                               X6CWo5JcxGI6S2DYeuHzsTXQDQ1SM5tdKtcJ2DaruJo.")]
+    fn witness_update_invalid() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        let genesis_opid = issue().genesis_opid();
+
+        let reader = ItemReader::new(vec![
+            OperationSeals {
+                operation: genesis_op,
+                defined_seals: small_bmap! { 0 => SEAL_1 },
+                witness: None,
+            }
+            .into(),
+            StreamItem::Witness(genesis_opid, SealWitness::new(strict_dumb!(), strict_dumb!())),
+        ]);
+        contract().evaluate(reader).unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "contract id contract:AAAAAAAA-AAAAAAA-AAAAAAA-AAAAAAA-AAAAAAA-AAAAAAA does not match \
+                               the id contract:p~YYi3qW-4q2KkFI-0_1oV9U-rYshHOq-pMjdvPV-70cacSI derived from the \
+                               contract genesis.")]
+    fn contract_id_mismatch() {
+        let genesis = genesis();
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+
         let mut contract = contract();
-        contract.evaluate(reader.clone())?;
+        contract.contract_id = ContractId::strict_dumb();
+        let reader =
+            TestReader::new(vec![OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None }]);
+        contract.evaluate(reader).unwrap();
+    }
 
-        // Check contract values
-        let mut ops = bmap! {};
-        let mut seals = bmap! {};
-        let mut witnesses = bmap! {};
-        for entry in reader.0 {
-            let opid = entry.operation.opid();
-            ops.insert(opid, entry.operation);
-            seals.insert(opid, entry.defined_seals.into_iter().collect());
-            witnesses.insert(opid, entry.witness.into_iter().collect());
-        }
+    #[test]
+    #[should_panic(expected = "genesis must not have inputs or an operation witness.")]
+    fn genesis_inputs() {
+        let genesis = genesis();
+        let mut genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+        genesis_op.witness = StateValue::Single { first: fe256::ZERO };
 
-        ops.pop_first();
-        let (genesis_opid, genesis_op) = contract.known_ops.first_key_value().unwrap();
-        ops.insert(*genesis_opid, genesis_op.clone());
-        seals.pop_first();
-        let (genesis_opid, definitions) = contract.seal_definitions.first_key_value().unwrap();
-        seals.insert(*genesis_opid, definitions.clone());
-        witnesses.pop_first();
-        let (genesis_opid, definitions) = contract.witnesses.first_key_value().unwrap();
-        witnesses.insert(*genesis_opid, definitions.clone());
+        let reader =
+            TestReader::new(vec![OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None }]);
+        run(reader).unwrap();
+    }
+
+    #[test]
+    fn genesis_record() {
+        let genesis = GenesisSeals {
+            genesis: genesis(),
+            defined_seals: small_bmap! { 0 => SEAL_1 },
+        };
+        let genesis_opid = issue().genesis_opid();
+
+        let mut contract = contract();
+        let reader = TestReader::new(vec![]).with_genesis(genesis);
+        contract.evaluate(reader).unwrap();
+        assert_eq!(contract.seal_definitions[&genesis_opid], map! { 0 => SEAL_1 });
+    }
+
+    #[test]
+    fn repeated_genesis() {
+        let genesis = GenesisSeals::<TxoSeal> { genesis: genesis(), defined_seals: none!() };
+        let repeated = genesis.to_operation_seals();
+        let opid = repeated.operation.opid();
+
+        let reader = TestReader::new(vec![repeated]).with_genesis(genesis.clone());
+        let err = contract().evaluate(reader).unwrap_err();
+        assert!(matches!(err, VerificationError::RepeatedGenesis(id) if id == opid));
+
+        let repeated = genesis.genesis.to_operation(contract().contract_id);
+        let opid = repeated.opid();
+        let reader =
+            TestReader::new(vec![OperationSeals { operation: repeated, defined_seals: none!(), witness: None }])
+                .with_genesis(genesis);
+        let err = contract().evaluate(reader).unwrap_err();
+        assert!(matches!(err, VerificationError::RepeatedGenesis(id) if id == opid));
+    }
+
+    #[test]
+    fn strict_genesis() {
+        let genesis = GenesisSeals::<TxoSeal> { genesis: genesis(), defined_seals: none!() };
+        let config = VerifyConfig { genesis_call: Some(0), ..default!() };
+        let reader = TestReader::new(vec![]).with_genesis(genesis.clone());
+        contract().evaluate_with(reader, &config).unwrap();
+
+        let config = VerifyConfig { genesis_call: Some(1), ..default!() };
+        let reader = TestReader::new(vec![]).with_genesis(genesis);
+        let err = contract().evaluate_with(reader, &config).unwrap_err();
+        assert!(matches!(err, VerificationError::GenesisCall { expected: 1, found: 0 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "genesis doesn't pass the codex verification.")]
+    fn strict_genesis_invalid() {
+        let mut genesis = genesis();
+        genesis.call_id = 1;
+        let mut contract: TestContract = contract_with(genesis.clone());
+        let config = VerifyConfig { genesis_call: Some(1), ..default!() };
+        let reader = TestReader::new(vec![]).with_genesis(GenesisSeals { genesis, defined_seals: none!() });
+        contract.evaluate_with(reader, &config).unwrap();
+    }
+
+    pub(crate) fn reading_operation(addr: CellAddr) -> Operation {
+        let mut operation = operation();
+        operation.destructible_in = none!();
+        operation.immutable_in = small_vec![addr];
+        operation
+    }
+
+    #[test]
+    fn mock_history() {
+        let cell = |seal: MockSealDef| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+        let spending = |contract_id: ContractId, addr: CellAddr, seal: MockSealDef| {
+            let mut operation = operation();
+            operation.contract_id = contract_id;
+            operation.destructible_in[0].addr = addr;
+            operation.destructible_out = small_vec![cell(seal)];
+            operation
+        };
+
+        let issued = MockSealDef::from(MockSeal::new(0, 0));
+        let mut genesis = genesis();
+        genesis.destructible_out[0] = cell(issued);
+        let mut contract: TestContract<MockSeal> = contract_with(genesis.clone());
+        let genesis_opid = genesis.opid(contract.contract_id);
+
+        let first = spending(contract.contract_id, CellAddr::new(genesis_opid, 0), MockSealDef::Wout(0));
+        let first_opid = first.opid();
+        let second = spending(contract.contract_id, CellAddr::new(first_opid, 0), MockSealDef::Wout(0));
+        let second_opid = second.opid();
+
+        let genesis = GenesisSeals { genesis, defined_seals: small_bmap! { 0 => issued } };
+        let reader = TestReader::new(vec![
+            OperationSeals {
+                operation: first,
+                defined_seals: small_bmap! { 0 => MockSealDef::Wout(0) },
+                witness: Some(MockWitness::new(1, [MockSeal::new(0, 0)], first_opid).seal_witness()),
+            },
+            OperationSeals {
+                operation: second,
+                defined_seals: small_bmap! { 0 => MockSealDef::Wout(0) },
+                witness: Some(MockWitness::new(2, [MockSeal::new(1, 0)], second_opid).seal_witness()),
+            },
+        ])
+        .with_genesis(genesis);
+        contract.evaluate(reader).unwrap();
+        assert!(contract.is_known(first_opid));
+        assert!(contract.is_known(second_opid));
+        assert_eq!(contract.witnesses[&second_opid][0].published.id, 2);
+    }
+
+    #[test]
+    fn duplicate_input() {
+        let issued = MockSealDef::from(MockSeal::new(0, 0));
+        let mut genesis = genesis();
+        genesis.destructible_out[0] = StateCell {
+            data: StateValue::None,
+            auth: issued.auth_token(),
+            lock: None,
+        };
+        let mut contract = contract_with::<MockSeal>(genesis.clone());
+        let genesis_addr = CellAddr::new(genesis.opid(contract.contract_id), 0);
+        let genesis = GenesisSeals { genesis, defined_seals: small_bmap! { 0 => issued } };
+
+        let mut operation = operation();
+        operation.contract_id = contract.contract_id;
+        operation.destructible_in = small_vec![Input { addr: genesis_addr, witness: StateValue::None }; 2];
+        let opid = operation.opid();
+        let witness = MockWitness::new(1, [MockSeal::new(0, 0), MockSeal::new(0, 0)], opid).seal_witness();
+        let reader =
+            TestReader::new(vec![OperationSeals { operation, defined_seals: none!(), witness: Some(witness) }])
+                .with_genesis(genesis);
+        let err = contract.evaluate(reader).unwrap_err();
+        assert!(matches!(err, VerificationError::DuplicateInput(id, addr) if id == opid && addr == genesis_addr));
+    }
+
+    #[test]
+    fn witness_update() {
+        let cell = |seal: MockSealDef| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+        let spending = |contract_id: ContractId, addr: CellAddr| {
+            let mut operation = operation();
+            operation.contract_id = contract_id;
+            operation.destructible_in[0].addr = addr;
+            operation.destructible_out = small_vec![cell(MockSealDef::Wout(0))];
+            operation
+        };
+
+        let issued = MockSealDef::from(MockSeal::new(0, 0));
+        let mut genesis = genesis();
+        genesis.destructible_out[0] = cell(issued);
+        let contract_id = contract_with::<MockSeal>(genesis.clone()).contract_id;
+        let genesis_opid = genesis.opid(contract_id);
+        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+
+        let first = spending(contract_id, CellAddr::new(genesis_opid, 0));
+        let first_opid = first.opid();
+        let second = spending(contract_id, CellAddr::new(first_opid, 0));
+        let second_opid = second.opid();
+
+        let items = |replaced_at: usize, closed_by: u64| {
+            let mut items = vec![
+                OperationSeals {
+                    operation: genesis_op.clone(),
+                    defined_seals: small_bmap! { 0 => issued },
+                    witness: None,
+                }
+                .into(),
+                OperationSeals {
+                    operation: first.clone(),
+                    defined_seals: small_bmap! { 0 => MockSealDef::Wout(0) },
+                    witness: Some(MockWitness::new(1, [MockSeal::new(0, 0)], first_opid).seal_witness()),
+                }
+                .into(),
+                OperationSeals {
+                    operation: second.clone(),
+                    defined_seals: none!(),
+                    witness: Some(MockWitness::new(3, [MockSeal::new(closed_by, 0)], second_opid).seal_witness()),
+                }
+                .into(),
+            ];
+            let replacement = MockWitness::new(2, [MockSeal::new(0, 0)], first_opid).seal_witness();
+            items.insert(replaced_at, StreamItem::Witness(first_opid, replacement));
+            ItemReader::<MockSeal>::new(items)
+        };
+
+        // The seal of the first operation is resolved with the replacement witness
+        let mut contract = contract_with::<MockSeal>(genesis.clone());
+        contract.evaluate(items(2, 2)).unwrap();
+        assert!(contract.is_known(second_opid));
+        assert_eq!(contract.witnesses[&first_opid].last().unwrap().published.id, 2);
+
+        let mut contract = contract_with::<MockSeal>(genesis.clone());
+        let err = contract.evaluate(items(2, 1)).unwrap_err();
+        assert!(matches!(err, VerificationError::SealsNotClosed(3, opid, _) if opid == second_opid));
+
+        // The seal of the first operation is already spent under the previous witness
+        let mut contract = contract_with::<MockSeal>(genesis);
+        let err = contract.evaluate(items(3, 1)).unwrap_err();
+        assert!(matches!(err, VerificationError::WitnessReplacement { opid, spent }
+            if opid == first_opid && spent == CellAddr::new(first_opid, 0)));
+    }
+
+    pub(crate) fn opret_witness(spent: Outpoint, opid: Opid) -> SealWitness<TxoSeal> {
+        let msg = mmb::Message::from_byte_array(opid.to_byte_array());
+        let bundle = mmb::BundleProof { map: small_bmap! { 0 => msg } };
+        let protocol = mpc::ProtocolId::from_byte_array([0xADu8; 32]);
+        let source = mpc::Source {
+            min_depth: u5::with(3),
+            entropy: 0xFE,
+            messages: MessageMap::from(Confined::from_checked(
+                bmap! { protocol => MessageSource::Mmb(bundle.clone()) },
+            )),
+        };
+        let merkle_tree = source.into_merkle_tree().unwrap();
+        let (_, mpc_proof) = merkle_tree.clone().into_proofs().next().unwrap();
+        let anchor = Anchor {
+            mmb_proof: bundle,
+            mpc_protocol: protocol,
+            mpc_proof,
+            dbc_proof: None,
+            fallback_proof: default!(),
+        };
+
+        let mut tx = Tx::strict_dumb();
+        tx.inputs
+            .push(TxIn {
+                prev_output: spent,
+                sig_script: none!(),
+                sequence: SeqNo::from_consensus_u32(0),
+                witness: none!(),
+            })
+            .unwrap();
+        tx.outputs
+            .push(TxOut {
+                value: Sats::ZERO,
+                script_pubkey: ScriptPubkey::op_return(merkle_tree.commit_id().as_slice()),
+            })
+            .unwrap();
+        SealWitness::new(tx, anchor)
+    }
+
+    #[test]
+    fn fallback() {
+        let primary = Outpoint::new(Txid::from_byte_array([1; 32]), 0);
+        let secondary = Outpoint::new(Txid::from_byte_array([2; 32]), 0);
+        let seal = WTxoSeal {
+            primary: WOutpoint::Extern(primary),
+            secondary: TxoSealExt::Fallback(secondary),
+        };
+        let mut genesis = genesis();
+        genesis.destructible_out[0].auth = seal.auth_token();
+        #[allow(clippy::result_large_err)]
+        let evaluate = |witness: &dyn Fn(Opid) -> SealWitness<TxoSeal>| {
+            let mut contract: TestContract = contract_with(genesis.clone());
+            let mut operation = operation();
+            operation.contract_id = contract.contract_id;
+            operation.destructible_in[0].addr = CellAddr::new(genesis.opid(contract.contract_id), 0);
+            let opid = operation.opid();
+            let genesis = GenesisSeals {
+                genesis: genesis.clone(),
+                defined_seals: small_bmap! { 0 => seal },
+            };
+            let block = OperationSeals {
+                operation,
+                defined_seals: none!(),
+                witness: Some(witness(opid)),
+            };
+            let reader = TestReader::new(vec![block]).with_genesis(genesis);
+            contract
+                .evaluate_with(reader, &default!())
+                .map(|report| (opid, report))
+        };
+
+        let (opid, report) = evaluate(&|opid| opret_witness(primary, opid)).unwrap();
+        assert_eq!(
+            report.commitments,
+            bmap! { opid => WitnessCommitment { method: CommitmentMethod::Opret, output: 0 } }
+        );
+
+        // The primary seal remains unspent, thus its fallback can't be closed
+        let err = evaluate(&|opid| opret_witness(secondary, opid)).unwrap_err();
+        assert!(matches!(err, VerificationError::FallbackUnproven(_, _, closed) if closed.primary == primary));
+
+        let unrelated = Outpoint::new(Txid::from_byte_array([3; 32]), 0);
+        let err = evaluate(&|opid| opret_witness(unrelated, opid)).unwrap_err();
+        assert!(matches!(err, VerificationError::FallbackNotClosed(_, _, closed) if closed.primary == primary));
+
+        let err = evaluate(&|opid| {
+            let mut witness = opret_witness(primary, opid);
+            witness.published.outputs[0].script_pubkey = ScriptPubkey::op_return(&[0u8; 32]);
+            witness
+        })
+        .unwrap_err();
+        assert!(matches!(err, VerificationError::SealsNotClosed(_, _, SealError::Published(_))));
+    }
+
+    #[test]
+    fn multi_layer() {
+        type Seal = MultiSeal<TxoSeal, MockSeal>;
+        type SealDef = MultiSealDef<TxoSeal, MockSeal>;
+        let cell = |seal: &SealDef| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+
+        let outpoint = Outpoint::new(Txid::from_byte_array([1; 32]), 0);
+        let issued = SealDef::First(WTxoSeal {
+            primary: WOutpoint::Extern(outpoint),
+            secondary: SEAL_1.secondary,
+        });
+        let mut genesis = genesis();
+        genesis.destructible_out[0] = cell(&issued);
+        let mut contract: TestContract<Seal> = contract_with(genesis.clone());
+        let genesis_addr = CellAddr::new(genesis.opid(contract.contract_id), 0);
+        let genesis = GenesisSeals { genesis, defined_seals: small_bmap! { 0 => issued } };
+
+        // Move the state from bitcoin to the mock layer
+        let peg_in = SealDef::Second(MockSealDef::Extern(MockSeal::new(0, 0)));
+        let mut first = operation();
+        first.contract_id = contract.contract_id;
+        first.destructible_in[0].addr = genesis_addr;
+        first.destructible_out = small_vec![cell(&peg_in)];
+        let first_opid = first.opid();
+        let first_witness = Seal::first_witness(opret_witness(outpoint, first_opid));
+        let first_id = first_witness.published.pub_id();
+
+        // Spend on the mock layer, defining a seal relative to the mock witness
+        let wout = SealDef::Second(MockSealDef::Wout(0));
+        let mut second = operation();
+        second.contract_id = contract.contract_id;
+        second.destructible_in[0].addr = CellAddr::new(first_opid, 0);
+        second.destructible_out = small_vec![cell(&wout)];
+        let second_opid = second.opid();
+        let second_witness =
+            Seal::second_witness(MockWitness::new(1, [MockSeal::new(0, 0)], second_opid).seal_witness());
+
+        let blocks = vec![
+            OperationSeals {
+                operation: first.clone(),
+                defined_seals: small_bmap! { 0 => peg_in },
+                witness: Some(first_witness.clone()),
+            },
+            OperationSeals {
+                operation: second,
+                defined_seals: small_bmap! { 0 => wout },
+                witness: Some(second_witness),
+            },
+        ];
+        let reader = TestReader::new(blocks).with_genesis(genesis.clone());
+        contract.evaluate(reader).unwrap();
+        assert_eq!(contract.witnesses[&first_opid][0].published.pub_id(), first_id);
+        assert!(matches!(contract.witnesses[&second_opid][0].published.pub_id(), MultiWitnessId::Second(1)));
+
+        // A bitcoin witness can't define seals relative to itself on the mock layer
+        let mut contract: TestContract<Seal> = contract_with(genesis.genesis.clone());
+        first.destructible_out = small_vec![cell(&wout)];
+        let first_opid = first.opid();
+        let block = OperationSeals {
+            operation: first,
+            defined_seals: small_bmap! { 0 => wout },
+            witness: Some(Seal::first_witness(opret_witness(outpoint, first_opid))),
+        };
+        let reader = TestReader::new(vec![block]).with_genesis(genesis);
+        let err = contract.evaluate(reader).unwrap_err();
+        assert!(matches!(err, VerificationError::UnspendableSeal { addr, .. } if addr == CellAddr::new(first_opid, 0)));
+    }
 
-        assert_eq!(ops, contract.known_ops);
-        assert_eq!(seals, contract.seal_definitions);
-        assert_eq!(witnesses, contract.witnesses);
-        Ok(())
+    #[test]
+    fn multi_layer_resolve() {
+        type Seal = MultiSeal<MockSeal, MockSeal>;
+        type SealDef = MultiSealDef<MockSeal, MockSeal>;
+        let opid = Opid::from_byte_array([0xAD; 32]);
+        let seals = small_bmap! {
+            0 => SealDef::Second(MockSealDef::Wout(1)),
+            1 => SealDef::Second(MockSealDef::Extern(MockSeal::new(3, 1))),
+            2 => SealDef::First(MockSealDef::Wout(2))
+        };
+
+        // A witness of the first layer can't define seals relative to itself on the second layer
+        let sources = Evaluation::<Seal>::resolve_seals(opid, &seals, Some(MultiWitnessId::First(5)));
+        assert_eq!(sources, bmap! {
+            CellAddr::new(opid, 1) => Seal::Second(MockSeal::new(3, 1)),
+            CellAddr::new(opid, 2) => Seal::First(MockSeal::new(5, 2))
+        });
     }
 
     #[test]
-    fn empty() {
-        let reader = TestReader::new(vec![]);
-        run(reader).unwrap();
+    fn late_unspendable() {
+        let outpoint = Outpoint::new(Txid::from_byte_array([1; 32]), 0);
+        let issued = WTxoSeal {
+            primary: WOutpoint::Extern(outpoint),
+            secondary: SEAL_1.secondary,
+        };
+        let wout = WTxoSeal {
+            primary: WOutpoint::Wout(Vout::from_u32(0)),
+            secondary: SEAL_1.secondary,
+        };
+        let mut genesis = genesis();
+        genesis.destructible_out[0].auth = issued.auth_token();
+        let mut contract: TestContract = contract_with(genesis.clone());
+        let genesis_opid = genesis.opid(contract.contract_id);
+        let genesis = GenesisSeals { genesis, defined_seals: small_bmap! { 0 => issued } };
+
+        let mut operation = operation();
+        operation.contract_id = contract.contract_id;
+        operation.destructible_in[0].addr = CellAddr::new(genesis_opid, 0);
+        operation.destructible_out =
+            small_vec![StateCell { data: StateValue::None, auth: wout.auth_token(), lock: None }];
+        let opid = operation.opid();
+        // The only output of the witness is an `OP_RETURN`, which can't be spent
+        let witness = opret_witness(outpoint, opid);
+        let witness_id = witness.published.pub_id();
+        let reader = ItemReader::new(vec![
+            genesis.to_operation_seals().into(),
+            OperationSeals { operation, defined_seals: none!(), witness: Some(witness) }.into(),
+            StreamItem::Seals(opid, small_bmap! { 0 => wout }),
+        ]);
+        let err = contract.evaluate(reader).unwrap_err();
+        assert!(matches!(err, VerificationError::UnspendableSeal { addr, witness }
+            if addr == CellAddr::new(opid, 0) && witness == witness_id));
     }
 
     #[test]
-    fn genesis_only() {
-        let genesis = genesis();
-        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+    fn seals_swapped() {
+        let cell = |seal: WTxoSeal| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+        let mut operation = reading_operation(CellAddr::new(issue().genesis_opid(), 0));
+        operation.destructible_out = small_vec![cell(SEAL_1), cell(SEAL_WOUT)];
+        let opid = operation.opid();
+
+        let genesis = GenesisSeals::<TxoSeal> { genesis: genesis(), defined_seals: none!() };
+        let block = OperationSeals {
+            operation,
+            defined_seals: small_bmap! { 0 => SEAL_WOUT, 1 => SEAL_1, 2 => SEAL_1 },
+            witness: None,
+        };
+        let reader = TestReader::new(vec![block]).with_genesis(genesis);
+        let err = contract().evaluate(reader).unwrap_err();
+        let VerificationError::SealsDefinitionMismatch { opid: id, reported, defined, sources } = err else {
+            panic!("unexpected error {err}")
+        };
+        assert_eq!(id, opid);
+        assert_eq!(reported, bmap! {
+            0 => SEAL_WOUT.auth_token(),
+            1 => SEAL_1.auth_token(),
+            2 => SEAL_1.auth_token()
+        });
+        assert_eq!(defined, bmap! { 0 => SEAL_1.auth_token(), 1 => SEAL_WOUT.auth_token() });
+        assert_eq!(sources.keys().copied().collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
 
-        let reader =
-            TestReader::new(vec![OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None }]);
-        run(reader).unwrap();
+    #[test]
+    fn seal_reuse() {
+        let genesis_opid = issue().genesis_opid();
+        let cell = StateCell {
+            data: StateValue::None,
+            auth: SEAL_1.auth_token(),
+            lock: None,
+        };
+        let mut operation = reading_operation(CellAddr::new(genesis_opid, 0));
+        operation.destructible_out = small_vec![cell];
+        let opid = operation.opid();
+
+        let genesis = GenesisSeals::<TxoSeal> {
+            genesis: genesis(),
+            defined_seals: small_bmap! { 0 => SEAL_1 },
+        };
+        let block = OperationSeals {
+            operation,
+            defined_seals: small_bmap! { 0 => SEAL_1 },
+            witness: None,
+        };
+        let reader = TestReader::new(vec![block]).with_genesis(genesis);
+        let err = contract().evaluate(reader).unwrap_err();
+        assert!(matches!(err, VerificationError::SealReuse { first, second }
+            if first == CellAddr::new(genesis_opid, 0) && second == CellAddr::new(opid, 0)));
     }
 
     #[test]
-    #[should_panic(expected = "genesis does not commit to the codex id; a wrong contract genesis is used.")]
-    fn invalid_genesis() {
-        let mut genesis = genesis();
-        genesis.codex_id = CodexId::from_byte_array([0xAD; 32]);
-        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+    fn seal_duplicate() {
+        let cell = StateCell {
+            data: StateValue::None,
+            auth: SEAL_1.auth_token(),
+            lock: None,
+        };
+        let mut operation = reading_operation(CellAddr::new(issue().genesis_opid(), 0));
+        operation.destructible_out = small_vec![cell, cell];
+        let opid = operation.opid();
+
+        let genesis = GenesisSeals::<TxoSeal> { genesis: genesis(), defined_seals: none!() };
+        let block = OperationSeals {
+            operation,
+            defined_seals: small_bmap! { 0 => SEAL_1, 1 => SEAL_1 },
+            witness: None,
+        };
+        let reader = TestReader::new(vec![block]).with_genesis(genesis);
+        let err = contract().evaluate(reader).unwrap_err();
+        assert!(matches!(err, VerificationError::SealReuse { first, second }
+            if first == CellAddr::new(opid, 0) && second == CellAddr::new(opid, 1)));
+    }
 
-        let reader =
-            TestReader::new(vec![OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None }]);
-        run(reader).unwrap();
+    #[test]
+    fn auth_token_collision() {
+        let genesis_opid = issue().genesis_opid();
+        let cell = |seal: WTxoSeal| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+        let mut operation = reading_operation(CellAddr::new(genesis_opid, 0));
+        operation.destructible_out = small_vec![cell(SEAL_WOUT), cell(SEAL_WOUT)];
+        let opid = operation.opid();
+
+        // The same seal definitions at different cells are not a collision
+        let genesis = GenesisSeals::<TxoSeal> { genesis: genesis(), defined_seals: none!() };
+        let block = OperationSeals {
+            operation,
+            defined_seals: small_bmap! { 0 => SEAL_WOUT, 1 => SEAL_WOUT },
+            witness: None,
+        };
+        let mut contract = contract();
+        contract
+            .global
+            .insert(CellAddr::new(genesis_opid, 0), genesis.genesis.immutable_out[0].value);
+        let reader = TestReader::new(vec![block]).with_genesis(genesis.clone());
+        contract.evaluate(reader).unwrap();
+
+        // Real collisions can't be found, thus we inject a different definition under the token
+        let first = CellAddr::new(genesis_opid, 0);
+        let tokens = bmap! { SEAL_1.auth_token() => (first, SEAL_WOUT) };
+        Evaluation::<TxoSeal>::check_tokens(&contract, &tokens, opid, &small_bmap! { 0 => SEAL_WOUT }).unwrap();
+        let err = Evaluation::<TxoSeal>::check_tokens(&contract, &tokens, opid, &small_bmap! {
+            0 => SEAL_WOUT,
+            1 => SEAL_1
+        })
+        .unwrap_err();
+        assert!(matches!(err, VerificationError::AuthTokenCollision { token, first: f, second }
+            if token == SEAL_1.auth_token() && f == first && second == CellAddr::new(opid, 1)));
+
+        // Collisions with the seal definitions known to the contract from previous evaluations
+        contract
+            .tokens
+            .insert(SEAL_1.auth_token(), (first, SEAL_WOUT));
+        let mut operation = reading_operation(CellAddr::new(genesis_opid, 0));
+        operation.destructible_out = small_vec![cell(SEAL_1)];
+        let opid = operation.opid();
+        let block = OperationSeals {
+            operation,
+            defined_seals: small_bmap! { 0 => SEAL_1 },
+            witness: None,
+        };
+        let reader = TestReader::new(vec![block]).with_genesis(genesis);
+        let err = contract.evaluate(reader).unwrap_err();
+        assert!(matches!(err, VerificationError::AuthTokenCollision { token, first: f, second }
+            if token == SEAL_1.auth_token() && f == first && second == CellAddr::new(opid, 0)));
     }
 
     #[test]
-    #[should_panic(expected = "seals, reported to be defined by the operation \
-                               k7fHvPyBlnM8m1n0QUaqNhB0I8kTwWXmi7nB_ZjTGVc, do match the assignments in the \
-                               operation.
-Actual operation seals from the assignments: {
-    AuthToken(
-        fe256(
-            0x0000141b74832b85ca7bc7e2899cc3e5617a29ac4340f09b105524a6f62bd597,
-        ),
-    ),
-}
-Reported seals: {
-    AuthToken(
-        fe256(
-            0x000046c31ad97975e90e4ab2ee247f0e2f39ec8461823023e977cc14bcda14f5,
-        ),
-    ),
-}
-Sources for the reported seals: {
-    0: \"~:0/00000000000000000000000000000000000000000000000000000000000000000000000000000000\",
-}")]
-    fn invalid_seals() {
-        let genesis = genesis();
-        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
+    fn paranoid_known_invalid() {
+        let genesis = GenesisSeals::<TxoSeal> { genesis: genesis(), defined_seals: none!() };
+        let mut operation = reading_operation(CellAddr::new(issue().genesis_opid(), 0));
+        operation.call_id = 1;
+        let opid = operation.opid();
+        let block = OperationSeals {
+            operation: operation.clone(),
+            defined_seals: none!(),
+            witness: None,
+        };
 
-        let reader = TestReader::new(vec![OperationSeals {
-            operation: genesis_op,
-            defined_seals: small_bmap! { 0 => WTxoSeal::strict_dumb() },
+        let mut contract = contract();
+        contract.known_ops.insert(opid, operation);
+        let reader = TestReader::new(vec![block.clone()]).with_genesis(genesis.clone());
+        contract.evaluate(reader).unwrap();
+
+        let config = VerifyConfig { paranoid: true, ..default!() };
+        let reader = TestReader::new(vec![block]).with_genesis(genesis);
+        let err = contract.evaluate_with(reader, &config).unwrap_err();
+        let VerificationError::KnownInvalid(invalid) = err else {
+            panic!("unexpected error {err}")
+        };
+        assert_eq!(invalid, bmap! { opid => CallError::NotFound(1) });
+    }
+
+    #[test]
+    fn paranoid_fresh_memory() {
+        let genesis = GenesisSeals::<TxoSeal> { genesis: genesis(), defined_seals: none!() };
+        let addr = CellAddr::new(Opid::strict_dumb(), 0);
+        let block = OperationSeals {
+            operation: reading_operation(addr),
+            defined_seals: none!(),
             witness: None,
-        }]);
-        run(reader).unwrap();
+        };
+
+        let mut trusting = contract();
+        trusting.global.insert(addr, StateValue::None);
+        let reader = TestReader::new(vec![block.clone()]).with_genesis(genesis.clone());
+        trusting.evaluate(reader).unwrap();
+
+        let mut contract = contract();
+        contract.global.insert(addr, StateValue::None);
+        let config = VerifyConfig { paranoid: true, ..default!() };
+        let reader = TestReader::new(vec![block]).with_genesis(genesis);
+        let err = contract.evaluate_with(reader, &config).unwrap_err();
+        assert!(matches!(err, VerificationError::Vm(CallError::NoImmutableInput(a)) if a == addr));
     }
 
     #[test]
-    #[should_panic(expected = "unknown seal definition for cell address k7fHvPyBlnM8m1n0QUaqNhB0I8kTwWXmi7nB_ZjTGVc:0.")]
-    fn seal_unknown() {
-        let genesis = genesis();
-        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
-        let operation = operation();
+    fn terminals() {
+        let genesis_seals = GenesisSeals::<TxoSeal> { genesis: genesis(), defined_seals: none!() };
+        let genesis_addr = CellAddr::new(issue().genesis_opid(), 0);
+        let contract = || {
+            let mut contract = contract();
+            contract
+                .global
+                .insert(genesis_addr, genesis().immutable_out[0].value);
+            contract
+        };
 
-        let reader = TestReader::new(vec![
-            OperationSeals { operation: genesis_op, defined_seals: none!(), witness: None },
-            OperationSeals { operation, defined_seals: none!(), witness: None },
-        ]);
-        run(reader).unwrap();
+        let mut paying = reading_operation(genesis_addr);
+        paying.destructible_out = small_vec![StateCell {
+            data: StateValue::None,
+            auth: SEAL_WOUT.auth_token(),
+            lock: None
+        }];
+        let paying = OperationSeals { operation: paying, defined_seals: none!(), witness: None };
+        let mut unrelated = reading_operation(genesis_addr);
+        unrelated.nonce = fe256::from(1u64);
+        let unrelated_opid = unrelated.opid();
+        let unrelated = OperationSeals { operation: unrelated, defined_seals: none!(), witness: None };
+
+        let config = VerifyConfig::default().expect_seals([SEAL_WOUT]);
+        let reader = TestReader::new(vec![paying.clone(), unrelated.clone()]).with_genesis(genesis_seals.clone());
+        contract().evaluate_with(reader, &config).unwrap();
+
+        let config = VerifyConfig::default().expect_seals([SEAL_WOUT, SEAL_1, WTxoSeal::strict_dumb()]);
+        let reader = TestReader::new(vec![paying.clone()]).with_genesis(genesis_seals.clone());
+        let err = contract().evaluate_with(reader, &config).unwrap_err();
+        assert!(
+            matches!(err, VerificationError::TerminalsMissing(missing) if missing == bset![WTxoSeal::strict_dumb().auth_token()])
+        );
+
+        let config = VerifyConfig { ancestors_only: true, ..default!() }.expect_seals([SEAL_WOUT]);
+        let reader = TestReader::new(vec![paying, unrelated]).with_genesis(genesis_seals);
+        let err = contract().evaluate_with(reader, &config).unwrap_err();
+        assert!(matches!(err, VerificationError::Unrelated(unrelated) if unrelated == bset![unrelated_opid]));
     }
 
     #[test]
-    #[should_panic(expected = "unknown seal definition for cell address k7fHvPyBlnM8m1n0QUaqNhB0I8kTwWXmi7nB_ZjTGVc:0.")]
-    fn genesis_with_wout() {
+    fn terminals_spent() {
+        let cell = |seal: MockSealDef| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+        let spending = |contract_id: ContractId, addr: CellAddr, seal: MockSealDef| {
+            let mut operation = operation();
+            operation.contract_id = contract_id;
+            operation.destructible_in[0].addr = addr;
+            operation.destructible_out = small_vec![cell(seal)];
+            operation
+        };
+
+        let issued = MockSealDef::from(MockSeal::new(0, 0));
+        let paid = MockSealDef::from(MockSeal::new(7, 0));
         let mut genesis = genesis();
-        genesis.destructible_out[0].auth = SEAL_WOUT.auth_token();
-        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
-        let operation = operation();
+        genesis.destructible_out[0] = cell(issued);
+        let contract = || contract_with::<MockSeal>(genesis.clone());
+        let contract_id = contract().contract_id;
+        let genesis_opid = genesis.opid(contract_id);
+
+        let first = spending(contract_id, CellAddr::new(genesis_opid, 0), MockSealDef::Wout(0));
+        let first_opid = first.opid();
+        let second = spending(contract_id, CellAddr::new(first_opid, 0), paid);
+        let second_opid = second.opid();
+        let reader = || {
+            TestReader::new(vec![
+                OperationSeals {
+                    operation: first.clone(),
+                    defined_seals: small_bmap! { 0 => MockSealDef::Wout(0) },
+                    witness: Some(MockWitness::new(1, [MockSeal::new(0, 0)], first_opid).seal_witness()),
+                },
+                OperationSeals {
+                    operation: second.clone(),
+                    defined_seals: small_bmap! { 0 => paid },
+                    witness: Some(MockWitness::new(2, [MockSeal::new(1, 0)], second_opid).seal_witness()),
+                },
+            ])
+            .with_genesis(GenesisSeals {
+                genesis: genesis.clone(),
+                defined_seals: small_bmap! { 0 => issued },
+            })
+        };
 
-        let reader = TestReader::new(vec![
-            OperationSeals {
-                operation: genesis_op,
-                defined_seals: small_bmap! { 0 => SEAL_WOUT},
-                witness: None,
-            },
-            OperationSeals { operation, defined_seals: none!(), witness: None },
-        ]);
-        run(reader).unwrap();
+        let config = VerifyConfig { ancestors_only: true, ..default!() }.expect_seals([paid]);
+        contract().evaluate_with(reader(), &config).unwrap();
+
+        // The seal defined by the first operation is already spent by the second one
+        let config = VerifyConfig::default().expect_seals([MockSealDef::Wout(0)]);
+        let err = contract().evaluate_with(reader(), &config).unwrap_err();
+        assert!(
+            matches!(err, VerificationError::TerminalsMissing(missing) if missing == bset![MockSealDef::Wout(0).auth_token()])
+        );
     }
 
     #[test]
-    #[should_panic(expected = "no witness known for the operation KAPb7ikgqk_ofCp1fZWm_T6XfKjuCwNf9BlZfPpoR0E.")]
-    fn no_witness() {
+    fn best_effort() {
         let genesis = genesis();
         let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
-        let operation = operation();
+        let genesis_addr = CellAddr::new(issue().genesis_opid(), 0);
 
-        let reader = TestReader::new(vec![
+        let mut invalid = operation();
+        invalid.destructible_out = small_vec![StateCell {
+            data: StateValue::None,
+            auth: SEAL_WOUT.auth_token(),
+            lock: None
+        }];
+        let invalid_opid = invalid.opid();
+        let mut descendant = operation();
+        descendant.destructible_in[0].addr = CellAddr::new(invalid_opid, 0);
+        let descendant_opid = descendant.opid();
+        let valid = reading_operation(genesis_addr);
+        let valid_opid = valid.opid();
+
+        let reader = ItemReader::new(vec![
             OperationSeals {
                 operation: genesis_op,
                 defined_seals: small_bmap! { 0 => SEAL_1 },
                 witness: None,
-            },
-            OperationSeals { operation, defined_seals: none!(), witness: None },
+            }
+            .into(),
+            OperationSeals { operation: invalid, defined_seals: none!(), witness: None }.into(),
+            OperationSeals { operation: descendant, defined_seals: none!(), witness: None }.into(),
+            OperationSeals { operation: valid, defined_seals: none!(), witness: None }.into(),
+            StreamItem::Witness(invalid_opid, SealWitness::new(strict_dumb!(), strict_dumb!())),
+            StreamItem::Seals(invalid_opid, small_bmap! { 0 => SEAL_WOUT }),
         ]);
-        run(reader).unwrap();
+        let mut contract = contract();
+        contract
+            .global
+            .insert(genesis_addr, genesis.immutable_out[0].value);
+        let config = VerifyConfig { best_effort: true, ..default!() };
+        let report = contract.evaluate_with(reader, &config).unwrap();
+
+        assert!(contract.known_ops.contains_key(&valid_opid));
+        assert!(!contract.known_ops.contains_key(&invalid_opid));
+        assert_eq!(report.quarantine.len(), 2);
+        let [invalid, descendant] = &report.quarantine[..] else {
+            unreachable!()
+        };
+        assert_eq!(invalid.opid, invalid_opid);
+        assert!(
+            matches!(invalid.reason, QuarantineReason::Invalid(VerificationError::NoWitness(id)) if id == invalid_opid)
+        );
+        assert!(invalid.block.witness.is_some());
+        assert_eq!(invalid.block.defined_seals.get(&0), Some(&SEAL_WOUT));
+        assert_eq!(descendant.opid, descendant_opid);
+        assert!(matches!(descendant.reason, QuarantineReason::Ancestor(id) if id == invalid_opid));
     }
 
     #[test]
-    #[should_panic(expected = "single-use seals are not closed properly with witness \
-                               4ebd325a4b394cff8c57e8317ccf5a8d0e2bdf1b8526f8aad6c8e43d8240621a for operation \
-                               KAPb7ikgqk_ofCp1fZWm_T6XfKjuCwNf9BlZfPpoR0E.
-Details: seal \
-                               0000000000000000000000000000000000000000000000000000000000000000:0/\
-                               0000000000000000000000000000000000000000000000000000000000000000:0 is not included in \
-                               the public witness 4ebd325a4b394cff8c57e8317ccf5a8d0e2bdf1b8526f8aad6c8e43d8240621a")]
-    fn seals_unclosed() {
-        let genesis = genesis();
-        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
-        let operation = operation();
+    fn pending() {
+        let genesis = GenesisSeals::<TxoSeal> {
+            genesis: genesis(),
+            defined_seals: small_bmap! { 0 => SEAL_1 },
+        };
+        let mut operation = operation();
+        operation.destructible_out = small_vec![StateCell {
+            data: StateValue::None,
+            auth: SEAL_WOUT.auth_token(),
+            lock: None
+        }];
+        let opid = operation.opid();
+        let block = OperationSeals {
+            operation,
+            defined_seals: small_bmap! { 0 => SEAL_WOUT },
+            witness: None,
+        };
+        let config = VerifyConfig { pending: true, ..default!() };
 
-        let reader = TestReader::new(vec![
-            OperationSeals {
-                operation: genesis_op,
-                defined_seals: small_bmap! { 0 => SEAL_1 },
-                witness: None,
-            },
-            OperationSeals {
-                operation,
-                defined_seals: none!(),
-                witness: Some(SealWitness::new(strict_dumb!(), strict_dumb!())),
-            },
+        let mut contract = contract();
+        let reader = TestReader::new(vec![block.clone()]).with_genesis(genesis.clone());
+        let report = contract.evaluate_with(reader, &config).unwrap();
+        assert_eq!(report.pending, bset![opid]);
+        assert!(contract.known_ops.contains_key(&opid));
+        assert!(!contract.witnesses.contains_key(&opid));
+        assert_eq!(contract.statuses, bmap! { opid => WitnessStatus::Tentative });
+
+        // The witness completing the pending operation must close its seals
+        let reader = ItemReader::new(vec![
+            genesis.to_operation_seals().into(),
+            block.into(),
+            StreamItem::Witness(opid, SealWitness::new(strict_dumb!(), strict_dumb!())),
         ]);
-        run(reader).unwrap();
+        let err = contract.evaluate_with(reader, &config).unwrap_err();
+        assert!(matches!(err, VerificationError::SealsNotClosed(_, id, _) if id == opid));
     }
 
     #[test]
-    #[should_panic(expected = "ingle-use seals are not closed properly with witness \
-                               1692606e775129a6733b6dc48ec7f5771f8e30d8c5304c0949d36efad2411812 for operation \
-                               KAPb7ikgqk_ofCp1fZWm_T6XfKjuCwNf9BlZfPpoR0E.
-Details: seal \
-                               0000000000000000000000000000000000000000000000000000000000000000:0/\
-                               0000000000000000000000000000000000000000000000000000000000000000:0 is not included in \
-                               the public witness 1692606e775129a6733b6dc48ec7f5771f8e30d8c5304c0949d36efad2411812")]
-    fn not_spending_utxo() {
-        let genesis = genesis();
-        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
-        let operation = operation();
-
-        let mut witness = Tx::strict_dumb();
-        witness
-            .outputs
-            .push(TxOut {
-                value: Sats::ZERO,
-                script_pubkey: ScriptPubkey::op_return(&[]),
-            })
-            .unwrap();
+    fn pending_completed() {
+        let cell = |seal: MockSealDef| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+        let spending = |contract_id: ContractId, addr: CellAddr| {
+            let mut operation = operation();
+            operation.contract_id = contract_id;
+            operation.destructible_in[0].addr = addr;
+            operation.destructible_out = small_vec![cell(MockSealDef::Wout(0))];
+            operation
+        };
 
-        let reader = TestReader::new(vec![
-            OperationSeals {
-                operation: genesis_op,
-                defined_seals: small_bmap! { 0 => SEAL_1 },
-                witness: None,
-            },
+        let issued = MockSealDef::from(MockSeal::new(0, 0));
+        let mut genesis = genesis();
+        genesis.destructible_out[0] = cell(issued);
+        let mut contract = contract_with::<MockSeal>(genesis.clone());
+        let contract_id = contract.contract_id;
+        let genesis_opid = genesis.opid(contract_id);
+        let genesis = GenesisSeals { genesis, defined_seals: small_bmap! { 0 => issued } };
+
+        let first = spending(contract_id, CellAddr::new(genesis_opid, 0));
+        let first_opid = first.opid();
+        let second = spending(contract_id, CellAddr::new(first_opid, 0));
+        let second_opid = second.opid();
+        let first = OperationSeals {
+            operation: first,
+            defined_seals: small_bmap! { 0 => MockSealDef::Wout(0) },
+            witness: None,
+        };
+        let config = VerifyConfig { pending: true, ..default!() };
+
+        let reader = ItemReader::new(vec![genesis.to_operation_seals().into(), first.clone().into()]);
+        let report = contract.evaluate_with(reader, &config).unwrap();
+        assert_eq!(report.pending, bset![first_opid]);
+        assert_eq!(contract.statuses, bmap! { first_opid => WitnessStatus::Tentative });
+
+        // The witness completes the pending operation, allowing to spend the seals it defines
+        let reader = ItemReader::new(vec![
+            genesis.to_operation_seals().into(),
+            first.into(),
+            StreamItem::Witness(first_opid, MockWitness::new(1, [MockSeal::new(0, 0)], first_opid).seal_witness()),
             OperationSeals {
-                operation,
+                operation: second,
                 defined_seals: none!(),
-                witness: Some(SealWitness::new(witness, strict_dumb!())),
-            },
+                witness: Some(MockWitness::new(2, [MockSeal::new(1, 0)], second_opid).seal_witness()),
+            }
+            .into(),
         ]);
-        run(reader).unwrap();
+        let report = contract.evaluate_with(reader, &config).unwrap();
+        assert!(report.pending.is_empty());
+        assert_eq!(contract.statuses, bmap! {
+            first_opid => WitnessStatus::Confirmed,
+            second_opid => WitnessStatus::Confirmed
+        });
+        assert_eq!(contract.witnesses[&first_opid][0].published.id, 1);
+    }
+
+    /// Resolver confirming a witness once it was polled the given number of times.
+    struct Mempool(RefCell<BTreeMap<u64, usize>>);
+    impl WitnessResolver<MockSeal> for Mempool {
+        fn witness_status(&self, witness_id: u64) -> WitnessStatus {
+            match self.0.borrow_mut().get_mut(&witness_id) {
+                Some(0) => WitnessStatus::Confirmed,
+                Some(polls) => {
+                    *polls -= 1;
+                    WitnessStatus::Tentative
+                }
+                None => WitnessStatus::Tentative,
+            }
+        }
     }
 
     #[test]
-    #[should_panic(expected = "single-use seals are not closed properly with witness \
-                               0520b790b442e9c023e2ea0e0e284fbe60086d64f01037082f19464b44f9642e for operation \
-                               KAPb7ikgqk_ofCp1fZWm_T6XfKjuCwNf9BlZfPpoR0E.
-Details: seal \
-                               0000000000000000000000000000000000000000000000000000000000000000:0/\
-                               0000000000000000000000000000000000000000000000000000000000000000:0 is not included in \
-                               the public witness 0520b790b442e9c023e2ea0e0e284fbe60086d64f01037082f19464b44f9642e")]
-    fn missing_commitment() {
-        let genesis = genesis();
-        let genesis_op = genesis.to_operation(genesis.codex_id.to_byte_array().into());
-        let operation = operation();
+    fn resolver_layers() {
+        let cell = |seal: MockSealDef| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+        let spending = |contract_id: ContractId, addr: CellAddr| {
+            let mut operation = operation();
+            operation.contract_id = contract_id;
+            operation.destructible_in[0].addr = addr;
+            operation.destructible_out = small_vec![cell(MockSealDef::Wout(0))];
+            operation
+        };
 
-        let mut witness = Tx::strict_dumb();
-        witness
-            .inputs
-            .push(TxIn {
-                prev_output: Outpoint::coinbase(),
-                sig_script: none!(),
-                sequence: SeqNo::from_consensus_u32(0),
-                witness: none!(),
-            })
+        let issued = MockSealDef::from(MockSeal::new(0, 0));
+        let mut genesis = genesis();
+        genesis.destructible_out[0] = cell(issued);
+        let mut contract = contract_with::<MockSeal>(genesis.clone());
+        let contract_id = contract.contract_id;
+        let genesis_opid = genesis.opid(contract_id);
+        let genesis = GenesisSeals { genesis, defined_seals: small_bmap! { 0 => issued } };
+
+        let first = spending(contract_id, CellAddr::new(genesis_opid, 0));
+        let first_opid = first.opid();
+        let second = spending(contract_id, CellAddr::new(first_opid, 0));
+        let second_opid = second.opid();
+        let items = || -> Vec<StreamItem<MockSeal>> {
+            vec![
+                genesis.to_operation_seals().into(),
+                OperationSeals {
+                    operation: first.clone(),
+                    defined_seals: small_bmap! { 0 => MockSealDef::Wout(0) },
+                    witness: Some(MockWitness::new(1, [MockSeal::new(0, 0)], first_opid).seal_witness()),
+                }
+                .into(),
+                OperationSeals {
+                    operation: second.clone(),
+                    defined_seals: small_bmap! { 0 => MockSealDef::Wout(0) },
+                    witness: Some(MockWitness::new(2, [MockSeal::new(1, 0)], second_opid).seal_witness()),
+                }
+                .into(),
+            ]
+        };
+        let config = VerifyConfig::default();
+
+        // A confirmed operation stays tentative while its ancestor is tentative
+        let resolver = Mempool(RefCell::new(bmap! { 2 => 0 }));
+        contract
+            .evaluate_layered(ItemReader::new(items()), &config, &resolver)
             .unwrap();
-        witness
-            .outputs
-            .push(TxOut {
-                value: Sats::ZERO,
-                script_pubkey: ScriptPubkey::op_return(&[]),
-            })
+        assert_eq!(contract.statuses, bmap! {
+            first_opid => WitnessStatus::Tentative,
+            second_opid => WitnessStatus::Tentative
+        });
+
+        // Re-evaluation promotes the operations once the ancestor witness gets confirmed
+        let resolver = Mempool(RefCell::new(bmap! { 1 => 0, 2 => 0 }));
+        contract
+            .evaluate_layered(ItemReader::new(items()), &config, &resolver)
             .unwrap();
+        assert_eq!(contract.statuses, bmap! {
+            first_opid => WitnessStatus::Confirmed,
+            second_opid => WitnessStatus::Confirmed
+        });
+    }
 
-        let reader = TestReader::new(vec![
-            OperationSeals {
-                operation: genesis_op,
-                defined_seals: small_bmap! { 0 => SEAL_1 },
-                witness: None,
-            },
+    #[test]
+    fn resolver_promotion() {
+        let cell = |seal: MockSealDef| StateCell { data: StateValue::None, auth: seal.auth_token(), lock: None };
+        let issued = MockSealDef::from(MockSeal::new(0, 0));
+        let mut genesis = genesis();
+        genesis.destructible_out[0] = cell(issued);
+        let mut contract = contract_with::<MockSeal>(genesis.clone());
+        let contract_id = contract.contract_id;
+        let genesis_opid = genesis.opid(contract_id);
+        let genesis = GenesisSeals { genesis, defined_seals: small_bmap! { 0 => issued } };
+
+        let mut first = operation();
+        first.contract_id = contract_id;
+        first.destructible_in[0].addr = CellAddr::new(genesis_opid, 0);
+        first.destructible_out = small_vec![cell(MockSealDef::Wout(0))];
+        let first_opid = first.opid();
+        let reader = ItemReader::new(vec![
+            genesis.to_operation_seals().into(),
             OperationSeals {
-                operation,
-                defined_seals: none!(),
-                witness: Some(SealWitness::new(witness, strict_dumb!())),
-            },
+                operation: first,
+                defined_seals: small_bmap! { 0 => MockSealDef::Wout(0) },
+                witness: Some(MockWitness::new(1, [MockSeal::new(0, 0)], first_opid).seal_witness()),
+            }
+            .into(),
         ]);
-        run(reader).unwrap();
+
+        // The witness gets confirmed while the stream is read; the operation is promoted at its end
+        let resolver = Mempool(RefCell::new(bmap! { 1 => 1 }));
+        contract
+            .evaluate_layered(reader, &VerifyConfig::default(), &resolver)
+            .unwrap();
+        assert_eq!(contract.statuses, bmap! { first_opid => WitnessStatus::Confirmed });
+    }
+
+    struct Unconfirmed;
+    impl WitnessResolver<TxoSeal> for Unconfirmed {
+        fn witness_status(&self, _: Txid) -> WitnessStatus { WitnessStatus::Tentative }
+    }
+
+    #[test]
+    fn layers() {
+        let genesis = GenesisSeals::<TxoSeal> {
+            genesis: genesis(),
+            defined_seals: small_bmap! { 0 => SEAL_1 },
+        };
+        let genesis_addr = CellAddr::new(issue().genesis_opid(), 0);
+
+        let mut pending = operation();
+        pending.immutable_out = small_vec![StateData::new(0u64, 1u64)];
+        let pending_opid = pending.opid();
+        let descendant = reading_operation(CellAddr::new(pending_opid, 0));
+        let descendant_opid = descendant.opid();
+        let confirmed = reading_operation(genesis_addr);
+        let confirmed_opid = confirmed.opid();
+
+        let mut contract = contract();
+        contract
+            .global
+            .insert(genesis_addr, genesis.genesis.immutable_out[0].value);
+        let reader = TestReader::new(vec![
+            OperationSeals { operation: pending, defined_seals: none!(), witness: None },
+            OperationSeals { operation: descendant, defined_seals: none!(), witness: None },
+            OperationSeals { operation: confirmed, defined_seals: none!(), witness: None },
+        ])
+        .with_genesis(genesis);
+        let config = VerifyConfig { pending: true, ..default!() };
+        contract
+            .evaluate_layered(reader, &config, &Unconfirmed)
+            .unwrap();
+
+        assert_eq!(contract.statuses, bmap! {
+            pending_opid => WitnessStatus::Tentative,
+            descendant_opid => WitnessStatus::Tentative,
+            confirmed_opid => WitnessStatus::Confirmed,
+        });
     }
 }